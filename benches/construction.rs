@@ -0,0 +1,50 @@
+//! Times the construction of large graphs, run it with
+//! `cargo bench --bench construction`.
+use std::time::{Duration, Instant};
+
+use onlinematching::{bigraph::Bigraph, weightedbigraph::WBigraph};
+
+fn time<T>(name: &str, edges: usize, build: impl FnOnce() -> T) -> Duration {
+    let start = Instant::now();
+    let graph = build();
+    let elapsed = start.elapsed();
+    // don't count the time to free the graph
+    drop(graph);
+    println!("{:<40} {:>9} edges {:>10.3?}", name, edges, elapsed);
+    elapsed
+}
+
+fn main() {
+    // the worst case of kvv90 Random, n = 2000 has 4_004_000 edges
+    let n = 2000;
+    time(
+        "kvv90::example::random_worst_case(2000)",
+        2 * n + n * n,
+        || onlinematching::papers::kvv90::example::random_worst_case(n),
+    );
+
+    // a million edges between 1000 offline and 1000 online nodes
+    let n = 1000;
+    let mut edges = Vec::with_capacity(n * n);
+    for v in 0..n {
+        for u in 0..n {
+            edges.push((u, v));
+        }
+    }
+    time("Bigraph::from_edges", edges.len(), || {
+        Bigraph::from_edges(&edges)
+    });
+
+    let weighted_edges = edges
+        .iter()
+        .map(|&(u, v)| ((u, v), (u + v) as f64))
+        .collect();
+    let elapsed = time("WBigraph::from_edges", edges.len(), || {
+        WBigraph::from_edges(&weighted_edges)
+    });
+    assert!(
+        elapsed < Duration::from_secs(10),
+        "building a million edges took {:?}",
+        elapsed
+    );
+}
@@ -0,0 +1,336 @@
+//! The index bookkeeping shared by `Bigraph` and `WBigraph`, generic over
+//! what the graph attaches to an edge: nothing for `Bigraph`, the weight
+//! for `WBigraph`.
+use std::collections::BTreeMap;
+
+use crate::error::{Error, Side};
+
+/// An entry of one of the edge or adjacency lists of a graph, the part
+/// `T` (a key pair, an index pair or the index of a neighbour) along with
+/// the data attached to the edge.
+pub(crate) trait Attached<T>: Copy {
+    type Data: Copy + PartialEq;
+
+    fn new(t: T, data: Self::Data) -> Self;
+
+    fn get(&self) -> T;
+
+    fn get_mut(&mut self) -> &mut T;
+
+    fn data(&self) -> Self::Data;
+}
+
+impl Attached<usize> for usize {
+    type Data = ();
+
+    fn new(t: usize, _data: ()) -> Self {
+        t
+    }
+
+    fn get(&self) -> usize {
+        *self
+    }
+
+    fn get_mut(&mut self) -> &mut usize {
+        self
+    }
+
+    fn data(&self) {}
+}
+
+impl<W: Copy + PartialEq> Attached<usize> for (usize, W) {
+    type Data = W;
+
+    fn new(t: usize, data: W) -> Self {
+        (t, data)
+    }
+
+    fn get(&self) -> usize {
+        self.0
+    }
+
+    fn get_mut(&mut self) -> &mut usize {
+        &mut self.0
+    }
+
+    fn data(&self) -> W {
+        self.1
+    }
+}
+
+impl<K: Copy> Attached<(K, K)> for (K, K) {
+    type Data = ();
+
+    fn new(t: (K, K), _data: ()) -> Self {
+        t
+    }
+
+    fn get(&self) -> (K, K) {
+        *self
+    }
+
+    fn get_mut(&mut self) -> &mut (K, K) {
+        self
+    }
+
+    fn data(&self) {}
+}
+
+impl<K: Copy, W: Copy + PartialEq> Attached<(K, K)> for ((K, K), W) {
+    type Data = W;
+
+    fn new(t: (K, K), data: W) -> Self {
+        (t, data)
+    }
+
+    fn get(&self) -> (K, K) {
+        self.0
+    }
+
+    fn get_mut(&mut self) -> &mut (K, K) {
+        &mut self.0
+    }
+
+    fn data(&self) -> W {
+        self.1
+    }
+}
+
+/// Mutable access to every field of a graph at once, so that the graphs
+/// only have to hand out their fields.
+pub(crate) struct GraphIndex<'a, Key, Edge, Pair, Adj> {
+    pub v_nodes: &'a mut Vec<Key>,
+    pub u_nodes: &'a mut Vec<Key>,
+    pub nodes_edges: &'a mut Vec<Edge>,
+    pub nodes_edges_use_index: &'a mut Vec<Pair>,
+    pub v_key2index: &'a mut BTreeMap<Key, usize>,
+    pub u_key2index: &'a mut BTreeMap<Key, usize>,
+    pub v_adjacency_list: &'a mut Vec<Vec<Adj>>,
+    pub u_adjacency_list: &'a mut Vec<Vec<Adj>>,
+}
+
+impl<Key, Data, Edge, Pair, Adj> GraphIndex<'_, Key, Edge, Pair, Adj>
+where
+    Key: Ord + Copy,
+    Data: Copy + PartialEq,
+    Edge: Attached<(Key, Key), Data = Data>,
+    Pair: Attached<(usize, usize), Data = Data>,
+    Adj: Attached<usize, Data = Data>,
+{
+    fn u_index(&self, key: &Key) -> Result<usize, Error<Key>> {
+        key_index(self.u_key2index, Side::Offline, key)
+    }
+
+    fn v_index(&self, key: &Key) -> Result<usize, Error<Key>> {
+        key_index(self.v_key2index, Side::Online, key)
+    }
+
+    /// Adds the edge, the duplicates are left to the caller.
+    pub fn push_edge(&mut self, u: Key, v: Key, data: Data) {
+        let v_index = self.v_index_or_insert(v);
+        let u_index = self.u_index_or_insert(u);
+        self.nodes_edges.push(Edge::new((u, v), data));
+        self.nodes_edges_use_index
+            .push(Pair::new((u_index, v_index), data));
+        self.v_adjacency_list[v_index].push(Adj::new(u_index, data));
+        self.u_adjacency_list[u_index].push(Adj::new(v_index, data));
+    }
+
+    pub fn insert_edge(&mut self, u: Key, v: Key, data: Data) -> Result<(), Error<Key>> {
+        if let (Ok(u_index), Ok(v_index)) = (self.u_index(&u), self.v_index(&v)) {
+            if self.v_adjacency_list[v_index]
+                .iter()
+                .any(|x| x.get() == u_index && x.data() == data)
+            {
+                return Err(Error::DuplicateEdge {
+                    offline: u,
+                    online: v,
+                });
+            }
+        }
+        self.push_edge(u, v, data);
+        Ok(())
+    }
+
+    /// The data of the first edge between `u` and `v`, if there is one.
+    pub fn edge_data(&self, u: &Key, v: &Key) -> Option<Data> {
+        let u_index = self.u_index(u).ok()?;
+        let v_index = self.v_index(v).ok()?;
+        self.v_adjacency_list[v_index]
+            .iter()
+            .find(|x| x.get() == u_index)
+            .map(|x| x.data())
+    }
+
+    /// Replaces the data of the edges between `u` and `v`.
+    pub fn set_edge_data(&mut self, u: &Key, v: &Key, data: Data) -> Result<(), Error<Key>> {
+        let u_index = self.u_index(u)?;
+        let v_index = self.v_index(v)?;
+        for i in 0..self.nodes_edges.len() {
+            if self.nodes_edges_use_index[i].get() == (u_index, v_index) {
+                self.nodes_edges[i] = Edge::new((*u, *v), data);
+                self.nodes_edges_use_index[i] = Pair::new((u_index, v_index), data);
+            }
+        }
+        for x in self.v_adjacency_list[v_index].iter_mut() {
+            if x.get() == u_index {
+                *x = Adj::new(u_index, data);
+            }
+        }
+        for x in self.u_adjacency_list[u_index].iter_mut() {
+            if x.get() == v_index {
+                *x = Adj::new(v_index, data);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the first edge between `u` and `v`.
+    pub fn remove_edge(&mut self, u: &Key, v: &Key) -> Result<(), Error<Key>> {
+        let u_index = self.u_index(u)?;
+        let v_index = self.v_index(v)?;
+        let position = self
+            .nodes_edges_use_index
+            .iter()
+            .position(|pair| pair.get() == (u_index, v_index))
+            .ok_or(Error::UnknownEdge {
+                offline: *u,
+                online: *v,
+            })?;
+        self.nodes_edges.remove(position);
+        let data = self.nodes_edges_use_index.remove(position).data();
+        remove_first(&mut self.v_adjacency_list[v_index], u_index, data);
+        remove_first(&mut self.u_adjacency_list[u_index], v_index, data);
+        Ok(())
+    }
+
+    pub fn remove_u(&mut self, u: &Key) -> Result<(), Error<Key>> {
+        let u_index = self.u_index(u)?;
+        self.retain_edges(|eu, _| eu != u_index);
+        self.u_nodes.remove(u_index);
+        self.u_adjacency_list.remove(u_index);
+        self.u_key2index.remove(u);
+        shift_down(self.u_key2index.values_mut(), u_index);
+        for online_adj in self.v_adjacency_list.iter_mut() {
+            online_adj.retain(|x| x.get() != u_index);
+            shift_down(online_adj.iter_mut().map(|x| x.get_mut()), u_index);
+        }
+        shift_down(
+            self.nodes_edges_use_index
+                .iter_mut()
+                .map(|pair| &mut pair.get_mut().0),
+            u_index,
+        );
+        Ok(())
+    }
+
+    pub fn remove_v(&mut self, v: &Key) -> Result<(), Error<Key>> {
+        let v_index = self.v_index(v)?;
+        self.retain_edges(|_, ev| ev != v_index);
+        self.v_nodes.remove(v_index);
+        self.v_adjacency_list.remove(v_index);
+        self.v_key2index.remove(v);
+        shift_down(self.v_key2index.values_mut(), v_index);
+        for offline_adj in self.u_adjacency_list.iter_mut() {
+            offline_adj.retain(|x| x.get() != v_index);
+            shift_down(offline_adj.iter_mut().map(|x| x.get_mut()), v_index);
+        }
+        shift_down(
+            self.nodes_edges_use_index
+                .iter_mut()
+                .map(|pair| &mut pair.get_mut().1),
+            v_index,
+        );
+        Ok(())
+    }
+
+    pub fn insert_u(&mut self, key: Key) -> Result<(), Error<Key>> {
+        if self.u_key2index.contains_key(&key) {
+            return Err(Error::DuplicateNode {
+                side: Side::Offline,
+                key,
+            });
+        }
+        self.u_index_or_insert(key);
+        Ok(())
+    }
+
+    pub fn insert_v(&mut self, key: Key) -> Result<(), Error<Key>> {
+        if self.v_key2index.contains_key(&key) {
+            return Err(Error::DuplicateNode {
+                side: Side::Online,
+                key,
+            });
+        }
+        self.v_index_or_insert(key);
+        Ok(())
+    }
+
+    pub fn v_index_or_insert(&mut self, key: Key) -> usize {
+        if let Some(&v_index) = self.v_key2index.get(&key) {
+            return v_index;
+        }
+        let v_index = self.v_nodes.len();
+        self.v_key2index.insert(key, v_index);
+        self.v_nodes.push(key);
+        self.v_adjacency_list.push(vec![]);
+        v_index
+    }
+
+    pub fn u_index_or_insert(&mut self, key: Key) -> usize {
+        if let Some(&u_index) = self.u_key2index.get(&key) {
+            return u_index;
+        }
+        let u_index = self.u_nodes.len();
+        self.u_key2index.insert(key, u_index);
+        self.u_nodes.push(key);
+        self.u_adjacency_list.push(vec![]);
+        u_index
+    }
+
+    // Keeps the edges (with their index pairs) for which `keep` holds,
+    // the adjacency lists are left to the caller.
+    fn retain_edges(&mut self, keep: impl Fn(usize, usize) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.nodes_edges.len() {
+            let (eu, ev) = self.nodes_edges_use_index[i].get();
+            if keep(eu, ev) {
+                self.nodes_edges.swap(kept, i);
+                self.nodes_edges_use_index.swap(kept, i);
+                kept += 1;
+            }
+        }
+        self.nodes_edges.truncate(kept);
+        self.nodes_edges_use_index.truncate(kept);
+    }
+}
+
+/// The index of the node with this key on `side`.
+pub(crate) fn key_index<Key: Ord + Copy>(
+    key2index: &BTreeMap<Key, usize>,
+    side: Side,
+    key: &Key,
+) -> Result<usize, Error<Key>> {
+    key2index
+        .get(key)
+        .copied()
+        .ok_or(Error::UnknownKey { side, key: *key })
+}
+
+fn remove_first<Adj: Attached<usize>>(adjacent: &mut Vec<Adj>, index: usize, data: Adj::Data) {
+    if let Some(position) = adjacent
+        .iter()
+        .position(|x| x.get() == index && x.data() == data)
+    {
+        adjacent.remove(position);
+    }
+}
+
+// The indices after the removed one move one down.
+fn shift_down<'a>(indices: impl Iterator<Item = &'a mut usize>, removed: usize) {
+    for index in indices {
+        if *index > removed {
+            *index -= 1;
+        }
+    }
+}
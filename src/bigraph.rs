@@ -1,4 +1,9 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::adjacency::{key_index, GraphIndex};
+use crate::error::{Error, Side};
+
+use crate::offline::matching::hopcroft_karp;
 
 type Edge<Key> = (Key, Key);
 
@@ -14,6 +19,12 @@ pub struct Bigraph<Key> {
     pub u_adjacency_list: Vec<Vec<usize>>,
 }
 
+impl<Key: Ord + Copy + std::fmt::Debug> Default for Bigraph<Key> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<Key: Ord + Copy + std::fmt::Debug> Bigraph<Key> {
     pub fn new() -> Bigraph<Key> {
         Bigraph {
@@ -28,69 +39,97 @@ impl<Key: Ord + Copy + std::fmt::Debug> Bigraph<Key> {
         }
     }
 
+    /// Panics on a duplicate edge, `try_from_edges` returns it as an `Error` instead.
+    /// Takes O(m log n) time for m edges and n nodes.
     pub fn from_edges(edges: &Vec<Edge<Key>>) -> Self {
+        Self::try_from_edges(edges).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_edges(edges: &Vec<Edge<Key>>) -> Result<Self, Error<Key>> {
         let mut graph = Self::new();
-        for edge in edges {
-            assert!(
-                !graph.nodes_edges.contains(edge),
-                "edges should't contain the same edge: {:?}",
-                edge
-            );
-            let (u, v) = edge;
-
-            let v_index;
+        graph.nodes_edges.reserve(edges.len());
+        graph.nodes_edges_use_index.reserve(edges.len());
+        // the index pairs of the edges seen so far, so that a duplicate
+        // is found without scanning all the edges
+        let mut seen = HashSet::with_capacity(edges.len());
+        let mut index = graph.index();
+        for &(u, v) in edges {
             // It means a new v node has arrived
             // so the adjacency_list and nodes list should be increased
-            if !graph.v_nodes.contains(v) {
-                v_index = graph.v_nodes.len();
-                graph.v_key2index.insert(v.clone(), v_index);
-                graph.v_nodes.push(v.clone());
-                graph.v_adjacency_list.push(vec![]);
-            } else {
-                v_index = graph.v_key2index[v];
-            }
-
+            let v_index = index.v_index_or_insert(v);
             // exactly the same as above
-            let u_index;
-            if !graph.u_nodes.contains(u) {
-                u_index = graph.u_nodes.len();
-                graph.u_key2index.insert(u.clone(), u_index);
-                graph.u_nodes.push(u.clone());
-                graph.u_adjacency_list.push(vec![]);
-            } else {
-                u_index = graph.u_key2index[u];
+            let u_index = index.u_index_or_insert(u);
+            if !seen.insert((u_index, v_index)) {
+                return Err(Error::DuplicateEdge {
+                    offline: u,
+                    online: v,
+                });
             }
+            index.push_edge(u, v, ());
+        }
+        Ok(graph)
+    }
 
-            graph.nodes_edges.push(edge.clone());
-            graph.nodes_edges_use_index.push((u_index, v_index));
+    /// The index of the offline node with this key.
+    pub fn u_index(&self, key: &Key) -> Result<usize, Error<Key>> {
+        key_index(&self.u_key2index, Side::Offline, key)
+    }
 
-            graph.v_adjacency_list[v_index].push(u_index);
-            graph.u_adjacency_list[u_index].push(v_index);
-        }
-        graph
+    /// The index of the online node with this key, i.e. its arrival order.
+    pub fn v_index(&self, key: &Key) -> Result<usize, Error<Key>> {
+        key_index(&self.v_key2index, Side::Online, key)
     }
 
-    pub fn insert_u(self: &mut Self, key: Key) -> Result<(), String> {
-        if self.u_nodes.contains(&key) {
-            Err("The u nodes already have this key".to_owned())
-        } else {
-            let u_index = self.u_nodes.len();
-            self.u_nodes.push(key);
-            self.u_adjacency_list.push(vec![]);
-            self.u_key2index.insert(key, u_index);
-            Ok(())
-        }
+    /// Adds the edge between `u` and `v`, the nodes which aren't in the
+    /// graph yet are added as in `from_edges`, so a new online node
+    /// arrives after all the others.
+    pub fn insert_edge(&mut self, u: Key, v: Key) -> Result<(), Error<Key>> {
+        self.index().insert_edge(u, v, ())
     }
 
-    pub fn insert_v(self: &mut Self, key: Key) -> Result<(), String> {
-        if self.v_nodes.contains(&key) {
-            Err("The v nodes already have this key".to_owned())
-        } else {
-            let v_index = self.v_nodes.len();
-            self.v_nodes.push(key);
-            self.v_adjacency_list.push(vec![]);
-            self.v_key2index.insert(key, v_index);
-            Ok(())
+    /// Removes the edge between `u` and `v`, both nodes stay in the graph.
+    pub fn remove_edge(&mut self, u: &Key, v: &Key) -> Result<(), Error<Key>> {
+        self.index().remove_edge(u, v)
+    }
+
+    /// Removes the offline node `u` with all its edges,
+    /// the offline nodes after it move one index down.
+    pub fn remove_u(&mut self, u: &Key) -> Result<(), Error<Key>> {
+        self.index().remove_u(u)
+    }
+
+    /// Removes the online node `v` with all its edges,
+    /// the online nodes arriving after it move one index down.
+    pub fn remove_v(&mut self, v: &Key) -> Result<(), Error<Key>> {
+        self.index().remove_v(v)
+    }
+
+    pub fn insert_u(&mut self, key: Key) -> Result<(), Error<Key>> {
+        self.index().insert_u(key)
+    }
+
+    pub fn insert_v(&mut self, key: Key) -> Result<(), Error<Key>> {
+        self.index().insert_v(key)
+    }
+
+    fn index(&mut self) -> GraphIndex<'_, Key, Edge<Key>, (usize, usize), usize> {
+        GraphIndex {
+            v_nodes: &mut self.v_nodes,
+            u_nodes: &mut self.u_nodes,
+            nodes_edges: &mut self.nodes_edges,
+            nodes_edges_use_index: &mut self.nodes_edges_use_index,
+            v_key2index: &mut self.v_key2index,
+            u_key2index: &mut self.u_key2index,
+            v_adjacency_list: &mut self.v_adjacency_list,
+            u_adjacency_list: &mut self.u_adjacency_list,
         }
     }
 }
+
+impl<Key> Bigraph<Key> {
+    /// Maximum matching of the graph, returns its size and the offline
+    /// node (as index) matched to every online node.
+    pub fn maximum_matching(&self) -> (usize, Vec<Option<usize>>) {
+        hopcroft_karp(self.u_nodes.len(), &self.v_adjacency_list)
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+
+/// The side of the bipartite graph a node belongs to,
+/// u nodes are offline and v nodes are online.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Offline,
+    Online,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Offline => write!(f, "offline"),
+            Side::Online => write!(f, "online"),
+        }
+    }
+}
+
+/// An invalid input when building or querying a graph or an instance.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error<Key> {
+    /// The graph already has a node with this key on this side.
+    DuplicateNode { side: Side, key: Key },
+    /// The graph already has an edge between these nodes, with the same
+    /// weight in a `WBigraph`.
+    DuplicateEdge { offline: Key, online: Key },
+    /// There must be exactly one budget for every offline node.
+    BudgetLengthMismatch { offline_size: usize, budgets: usize },
+    /// The probability of an edge isn't in [0, 1].
+    ProbabilityOutOfRange {
+        offline: Key,
+        online: Key,
+        prob: f64,
+    },
+    /// The graph has no node with this key on this side.
+    UnknownKey { side: Side, key: Key },
+    /// The graph has no edge between these nodes.
+    UnknownEdge { offline: Key, online: Key },
+    /// There must be exactly one arrival rate for every online type.
+    RateLengthMismatch { types: usize, rates: usize },
+    /// An arrival rate is negative or not finite.
+    InvalidRate { online: Key, rate: f64 },
+    /// All the arrival rates are 0, so no online type can arrive.
+    AllRatesZero,
+    /// There must be exactly one weight for every offline node.
+    WeightLengthMismatch { offline_size: usize, weights: usize },
+    /// The weight of an offline node is negative or not finite.
+    InvalidWeight { offline: Key, weight: f64 },
+    /// There must be exactly one capacity for every offline node.
+    CapacityLengthMismatch {
+        offline_size: usize,
+        capacities: usize,
+    },
+    /// The weight of an edge is negative or not finite.
+    InvalidEdgeWeight {
+        offline: Key,
+        online: Key,
+        weight: f64,
+    },
+    /// Online nodes must be offered to the offline nodes at least once.
+    InvalidPatience { patience: usize },
+    /// There must be exactly one usage duration for every offline node.
+    DurationLengthMismatch {
+        offline_size: usize,
+        durations: usize,
+    },
+    /// The distribution of the usage duration of an offline node is empty
+    /// or its probabilities don't add up to 1.
+    InvalidDuration { offline: Key },
+}
+
+impl<Key: fmt::Debug> fmt::Display for Error<Key> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DuplicateNode { side, key } => {
+                write!(f, "the {} nodes already have the key {:?}", side, key)
+            }
+            Error::DuplicateEdge { offline, online } => write!(
+                f,
+                "edges shouldn't contain the same edge: ({:?}, {:?})",
+                offline, online
+            ),
+            Error::BudgetLengthMismatch {
+                offline_size,
+                budgets,
+            } => write!(
+                f,
+                "{} budgets are given for {} offline nodes",
+                budgets, offline_size
+            ),
+            Error::ProbabilityOutOfRange {
+                offline,
+                online,
+                prob,
+            } => write!(
+                f,
+                "prob = {} of the edge ({:?}, {:?}), probability should be in [0, 1]",
+                prob, offline, online
+            ),
+            Error::UnknownKey { side, key } => {
+                write!(f, "there is no {} node with the key {:?}", side, key)
+            }
+            Error::UnknownEdge { offline, online } => {
+                write!(f, "there is no edge ({:?}, {:?})", offline, online)
+            }
+            Error::RateLengthMismatch { types, rates } => write!(
+                f,
+                "{} arrival rates are given for {} online types",
+                rates, types
+            ),
+            Error::InvalidRate { online, rate } => write!(
+                f,
+                "rate = {} of the online type {:?}, rates should be non-negative and finite",
+                rate, online
+            ),
+            Error::AllRatesZero => write!(f, "all the arrival rates are 0"),
+            Error::WeightLengthMismatch {
+                offline_size,
+                weights,
+            } => write!(
+                f,
+                "{} weights are given for {} offline nodes",
+                weights, offline_size
+            ),
+            Error::InvalidWeight { offline, weight } => write!(
+                f,
+                "weight = {} of the offline node {:?}, weights should be non-negative",
+                weight, offline
+            ),
+            Error::CapacityLengthMismatch {
+                offline_size,
+                capacities,
+            } => write!(
+                f,
+                "{} capacities are given for {} offline nodes",
+                capacities, offline_size
+            ),
+            Error::InvalidEdgeWeight {
+                offline,
+                online,
+                weight,
+            } => write!(
+                f,
+                "weight = {} of the edge ({:?}, {:?}), weights should be non-negative",
+                weight, offline, online
+            ),
+            Error::InvalidPatience { patience } => write!(
+                f,
+                "patience = {}, online nodes should be offered at least once",
+                patience
+            ),
+            Error::DurationLengthMismatch {
+                offline_size,
+                durations,
+            } => write!(
+                f,
+                "{} usage durations are given for {} offline nodes",
+                durations, offline_size
+            ),
+            Error::InvalidDuration { offline } => write!(
+                f,
+                "the usage duration of the offline node {:?} isn't a distribution",
+                offline
+            ),
+        }
+    }
+}
+
+impl<Key: fmt::Debug> std::error::Error for Error<Key> {}
@@ -1,3 +1,6 @@
+mod adjacency;
 pub mod bigraph;
+pub mod error;
+pub mod offline;
 pub mod papers;
 pub mod weightedbigraph;
@@ -0,0 +1,107 @@
+use super::lp::LinearProgram;
+
+// Every edge becomes one variable, in the order of the adjacency lists.
+fn edges(online_adjacency_list: &[Vec<(usize, f64)>]) -> Vec<(usize, usize, f64)> {
+    let mut edges = Vec::new();
+    for (v, adj) in online_adjacency_list.iter().enumerate() {
+        for &(u, bid) in adj.iter() {
+            edges.push((v, u, bid));
+        }
+    }
+    edges
+}
+
+/// The LP relaxation of budgeted allocation:
+///     maximize sum(b_uv * x_uv)
+///     s.t. sum_u x_uv <= 1 for every online v,
+///          sum_v b_uv * x_uv <= B_u for every offline u.
+/// It upper bounds the offline optimum and can be used on large instances.
+pub fn budgeted_allocation_lp(budgets: &[f64], online_adjacency_list: &[Vec<(usize, f64)>]) -> f64 {
+    let edges = edges(online_adjacency_list);
+    let mut lp = LinearProgram::new(edges.iter().map(|e| e.2).collect());
+    let mut online_rows = vec![vec![]; online_adjacency_list.len()];
+    let mut offline_rows = vec![vec![]; budgets.len()];
+    for (k, &(v, u, bid)) in edges.iter().enumerate() {
+        online_rows[v].push((k, 1.));
+        offline_rows[u].push((k, bid));
+    }
+    for row in online_rows {
+        lp.add_constraint(row, 1.);
+    }
+    for (u, row) in offline_rows.into_iter().enumerate() {
+        lp.add_constraint(row, budgets[u]);
+    }
+    lp.maximize().value()
+}
+
+/// The integral budgeted allocation, i.e. the offline AdWords optimum where
+/// an offline node earns min(B_u, sum of the bids assigned to it).
+/// Returns the revenue and the offline node every online node is assigned to.
+/// It runs a branch and bound, only use it on small instances.
+pub fn budgeted_allocation(
+    budgets: &[f64],
+    online_adjacency_list: &[Vec<(usize, f64)>],
+) -> (f64, Vec<Option<usize>>) {
+    let edges = edges(online_adjacency_list);
+    let l = edges.len();
+    // x_uv for every edge, then the capped revenue y_u of every offline node
+    let mut objective = vec![0.; l];
+    objective.resize(l + budgets.len(), 1.);
+    let mut lp = LinearProgram::new(objective);
+    let mut online_rows = vec![vec![]; online_adjacency_list.len()];
+    let mut offline_rows: Vec<Vec<(usize, f64)>> =
+        (0..budgets.len()).map(|u| vec![(l + u, 1.)]).collect();
+    for (k, &(v, u, bid)) in edges.iter().enumerate() {
+        online_rows[v].push((k, 1.));
+        offline_rows[u].push((k, -bid));
+    }
+    for row in online_rows {
+        lp.add_constraint(row, 1.);
+    }
+    for (u, row) in offline_rows.into_iter().enumerate() {
+        lp.add_constraint(row, 0.);
+        lp.add_constraint(vec![(l + u, 1.)], budgets[u]);
+    }
+    let result = lp.maximize_integral(&(0..l).collect::<Vec<_>>());
+    let mut assignment = vec![None; online_adjacency_list.len()];
+    if let super::lp::LpResult::Optimal(_, ref x) = result {
+        for (k, &(v, u, _)) in edges.iter().enumerate() {
+            if x[k] > 0.5 {
+                assignment[v] = Some(u);
+            }
+        }
+    }
+    (result.value(), assignment)
+}
+
+/// The LP relaxation of stochastic matching with offline weights and
+/// online patience k:
+///     maximize sum(w_u * p_uv * x_uv)
+///     s.t. sum_u x_uv <= k and sum_u p_uv * x_uv <= 1 for every online v,
+///          sum_v p_uv * x_uv <= 1 for every offline u,
+///          x_uv <= 1,
+/// where x_uv is the probability that (u, v) is probed. It upper bounds
+/// the expected reward of any adaptive policy.
+pub fn stochastic_matching_lp(
+    weights: &[f64],
+    patience: usize,
+    online_adjacency_list: &[Vec<(usize, f64)>],
+) -> f64 {
+    let edges = edges(online_adjacency_list);
+    let mut lp = LinearProgram::new(edges.iter().map(|&(_, u, p)| weights[u] * p).collect());
+    let mut online_rows = vec![vec![]; online_adjacency_list.len()];
+    let mut offline_rows = vec![vec![]; weights.len()];
+    for (k, &(v, u, p)) in edges.iter().enumerate() {
+        online_rows[v].push((k, p));
+        offline_rows[u].push((k, p));
+        lp.add_constraint(vec![(k, 1.)], 1.);
+    }
+    for row in online_rows {
+        lp.add_constraint(row.iter().map(|&(k, _)| (k, 1.)).collect(), patience as f64);
+        lp.add_constraint(row, 1.);
+    }
+    for row in offline_rows {
+        lp.add_constraint(row, 1.);
+    }
+    lp.maximize().value()
+}
@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+const EPS: f64 = 1e-9;
+
+/// A directed network with real capacities, solved by Dinic's algorithm.
+///
+/// Edges are numbered in the order they are added, `flow(edge)` gives the
+/// flow through an edge after `max_flow`.
+#[derive(Debug, Clone)]
+pub struct FlowNetwork {
+    // the edges leaving every node, as indices into `to` and `capacity`;
+    // edge 2k is the k-th added edge and 2k + 1 its residual reverse
+    out_edges: Vec<Vec<usize>>,
+    to: Vec<usize>,
+    capacity: Vec<f64>,
+}
+
+impl FlowNetwork {
+    pub fn new(size: usize) -> Self {
+        FlowNetwork {
+            out_edges: vec![vec![]; size],
+            to: vec![],
+            capacity: vec![],
+        }
+    }
+
+    /// Adds an edge and returns its index.
+    pub fn add_edge(&mut self, from: usize, to: usize, capacity: f64) -> usize {
+        let edge = self.to.len();
+        self.out_edges[from].push(edge);
+        self.to.push(to);
+        self.capacity.push(capacity);
+        self.out_edges[to].push(edge + 1);
+        self.to.push(from);
+        self.capacity.push(0.);
+        edge / 2
+    }
+
+    /// The flow through the `edge`-th added edge.
+    pub fn flow(&self, edge: usize) -> f64 {
+        // the residual capacity of the reverse edge, which starts at 0,
+        // also works for infinite capacities
+        self.capacity[2 * edge + 1]
+    }
+
+    /// Pushes a maximum flow from `source` to `sink` and returns its value,
+    /// in O(V^2 * E).
+    pub fn max_flow(&mut self, source: usize, sink: usize) -> f64 {
+        let mut total = 0.;
+        let mut level = vec![usize::MAX; self.out_edges.len()];
+        while self.bfs(source, sink, &mut level) {
+            let mut next_edge = vec![0; self.out_edges.len()];
+            loop {
+                let pushed = self.dfs(source, sink, f64::INFINITY, &level, &mut next_edge);
+                if pushed <= EPS {
+                    break;
+                }
+                total += pushed;
+            }
+        }
+        total
+    }
+
+    fn bfs(&self, source: usize, sink: usize, level: &mut [usize]) -> bool {
+        level.iter_mut().for_each(|l| *l = usize::MAX);
+        level[source] = 0;
+        let mut queue = VecDeque::from(vec![source]);
+        while let Some(node) = queue.pop_front() {
+            for &edge in self.out_edges[node].iter() {
+                let next = self.to[edge];
+                if self.capacity[edge] > EPS && level[next] == usize::MAX {
+                    level[next] = level[node] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        level[sink] != usize::MAX
+    }
+
+    fn dfs(
+        &mut self,
+        node: usize,
+        sink: usize,
+        limit: f64,
+        level: &Vec<usize>,
+        next_edge: &mut Vec<usize>,
+    ) -> f64 {
+        if node == sink {
+            return limit;
+        }
+        while next_edge[node] < self.out_edges[node].len() {
+            let edge = self.out_edges[node][next_edge[node]];
+            let next = self.to[edge];
+            if self.capacity[edge] > EPS && level[next] == level[node] + 1 {
+                let pushed = self.dfs(
+                    next,
+                    sink,
+                    f64::min(limit, self.capacity[edge]),
+                    level,
+                    next_edge,
+                );
+                if pushed > EPS {
+                    self.capacity[edge] -= pushed;
+                    self.capacity[edge ^ 1] += pushed;
+                    return pushed;
+                }
+            }
+            next_edge[node] += 1;
+        }
+        0.
+    }
+}
+
+/// The maximum fractional matching of a bipartite graph in which every
+/// offline node has capacity `offline_capacity[u]` and every online node
+/// `online_capacity[v]`. Returns its value and the flow on every edge, in
+/// the layout of `online_adjacency_list`.
+pub fn capacitated_matching(
+    offline_capacity: &[f64],
+    online_capacity: &[f64],
+    online_adjacency_list: &[Vec<usize>],
+) -> (f64, Vec<Vec<f64>>) {
+    let offline_size = offline_capacity.len();
+    let online_size = online_adjacency_list.len();
+    // source, online nodes, offline nodes, sink
+    let source = 0;
+    let sink = online_size + offline_size + 1;
+    let mut network = FlowNetwork::new(sink + 1);
+    for (v, &capacity) in online_capacity.iter().enumerate() {
+        network.add_edge(source, 1 + v, capacity);
+    }
+    for (u, &capacity) in offline_capacity.iter().enumerate() {
+        network.add_edge(1 + online_size + u, sink, capacity);
+    }
+    let edges: Vec<Vec<usize>> = online_adjacency_list
+        .iter()
+        .enumerate()
+        .map(|(v, online_adj)| {
+            online_adj
+                .iter()
+                .map(|&u| network.add_edge(1 + v, 1 + online_size + u, f64::INFINITY))
+                .collect()
+        })
+        .collect();
+    let value = network.max_flow(source, sink);
+    let flows = edges
+        .iter()
+        .map(|online_edges| online_edges.iter().map(|&e| network.flow(e)).collect())
+        .collect();
+    (value, flows)
+}
@@ -0,0 +1,299 @@
+const EPS: f64 = 1e-9;
+
+/// After this many pivots without improving the objective the entering
+/// variable is chosen by Bland's rule, so degenerate programs (matching
+/// polytopes are very degenerate) can't cycle.
+const DEGENERATE_PIVOTS: usize = 50;
+
+/// A linear program in the form
+///     maximize c^T x, s.t. A x <= b, x >= 0,
+/// solved by a dense two-phase simplex.
+/// `b` may be negative, the first phase then finds a feasible basis.
+///
+/// The tableau takes (constraints + 2) * (variables + 2) floats, and every
+/// pivot touches all of them: 10^4 constraints and 10^4 variables already
+/// take 800 MB. The programs of `offline` have a variable per edge, so
+/// they are meant for up to a few thousand edges.
+#[derive(Debug, Clone)]
+pub struct LinearProgram {
+    objective: Vec<f64>,
+    constraints: Vec<(Vec<(usize, f64)>, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LpResult {
+    Optimal(f64, Vec<f64>),
+    Infeasible,
+    Unbounded,
+}
+
+impl LinearProgram {
+    pub fn new(objective: Vec<f64>) -> Self {
+        LinearProgram {
+            objective,
+            constraints: vec![],
+        }
+    }
+
+    pub fn variables(&self) -> usize {
+        self.objective.len()
+    }
+
+    /// Adds the constraint sum(coefficient * x[index]) <= rhs,
+    /// `row` is sparse and may not mention every variable.
+    pub fn add_constraint(&mut self, row: Vec<(usize, f64)>, rhs: f64) {
+        for &(j, _) in row.iter() {
+            assert!(
+                j < self.variables(),
+                "variable {} out of {}",
+                j,
+                self.variables()
+            );
+        }
+        self.constraints.push((row, rhs));
+    }
+
+    pub fn maximize(&self) -> LpResult {
+        Tableau::new(self).solve()
+    }
+
+    /// Branch and bound over the variables in `integral`,
+    /// the other variables stay continuous.
+    /// It is exponential in the worst case, so only use it on small programs.
+    pub fn maximize_integral(&self, integral: &[usize]) -> LpResult {
+        let mut best: Option<(f64, Vec<f64>)> = None;
+        let mut unbounded = false;
+        let mut stack = vec![self.clone()];
+        while let Some(lp) = stack.pop() {
+            let (value, x) = match lp.maximize() {
+                LpResult::Optimal(value, x) => (value, x),
+                LpResult::Infeasible => continue,
+                LpResult::Unbounded => {
+                    unbounded = true;
+                    continue;
+                }
+            };
+            if let Some((best_value, _)) = best {
+                if value <= best_value + EPS {
+                    continue;
+                }
+            }
+            // branch on the most fractional variable
+            let mut branch = None;
+            let mut distance = EPS.sqrt();
+            for &j in integral.iter() {
+                let d = (x[j] - x[j].round()).abs();
+                if d > distance {
+                    distance = d;
+                    branch = Some(j);
+                }
+            }
+            match branch {
+                None => {
+                    let x = x
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &xj)| {
+                            if integral.contains(&j) {
+                                xj.round()
+                            } else {
+                                xj
+                            }
+                        })
+                        .collect();
+                    best = Some((value, x));
+                }
+                Some(j) => {
+                    let mut down = lp.clone();
+                    down.add_constraint(vec![(j, 1.)], x[j].floor());
+                    let mut up = lp;
+                    up.add_constraint(vec![(j, -1.)], -x[j].ceil());
+                    stack.push(down);
+                    stack.push(up);
+                }
+            }
+        }
+        match best {
+            Some((value, x)) => LpResult::Optimal(value, x),
+            None if unbounded => LpResult::Unbounded,
+            None => LpResult::Infeasible,
+        }
+    }
+}
+
+impl LpResult {
+    /// The optimal value, panics if the program has no optimum.
+    pub fn value(&self) -> f64 {
+        match self {
+            LpResult::Optimal(value, _) => *value,
+            LpResult::Infeasible => panic!("the linear program is infeasible"),
+            LpResult::Unbounded => panic!("the linear program is unbounded"),
+        }
+    }
+}
+
+// The tableau keeps the nonbasic variables in the columns 0..n,
+// column n belongs to the phase one artificial variable
+// and column n + 1 holds the right hand side.
+// Row m is the objective, row m + 1 the phase one objective.
+struct Tableau {
+    m: usize,
+    n: usize,
+    basic: Vec<isize>,
+    nonbasic: Vec<isize>,
+    d: Vec<Vec<f64>>,
+}
+
+impl Tableau {
+    fn new(lp: &LinearProgram) -> Self {
+        let m = lp.constraints.len();
+        let n = lp.variables();
+        let mut d = vec![vec![0.; n + 2]; m + 2];
+        for (i, (row, rhs)) in lp.constraints.iter().enumerate() {
+            for &(j, a) in row.iter() {
+                d[i][j] += a;
+            }
+            d[i][n] = -1.;
+            d[i][n + 1] = *rhs;
+        }
+        for (j, &c) in lp.objective.iter().enumerate() {
+            d[m][j] = -c;
+        }
+        d[m + 1][n] = 1.;
+        let basic = (0..m).map(|i| (n + i) as isize).collect();
+        let mut nonbasic: Vec<isize> = (0..n).map(|j| j as isize).collect();
+        nonbasic.push(-1);
+        Tableau {
+            m,
+            n,
+            basic,
+            nonbasic,
+            d,
+        }
+    }
+
+    fn pivot(&mut self, r: usize, s: usize) {
+        let inv = 1. / self.d[r][s];
+        let pivot_row = self.d[r].clone();
+        for i in 0..self.m + 2 {
+            if i == r || self.d[i][s].abs() < EPS * EPS {
+                continue;
+            }
+            let factor = self.d[i][s] * inv;
+            let row = &mut self.d[i];
+            for j in 0..self.n + 2 {
+                row[j] -= pivot_row[j] * factor;
+            }
+            row[s] = -factor;
+        }
+        for j in 0..self.n + 2 {
+            self.d[r][j] *= inv;
+        }
+        self.d[r][s] = inv;
+        std::mem::swap(&mut self.basic[r], &mut self.nonbasic[s]);
+    }
+
+    // Returns false if the objective of the phase is unbounded.
+    fn simplex(&mut self, phase: usize) -> bool {
+        let x = if phase == 1 { self.m + 1 } else { self.m };
+        let mut degenerate = 0;
+        loop {
+            let bland = degenerate > DEGENERATE_PIVOTS;
+            let mut s: Option<usize> = None;
+            for j in 0..=self.n {
+                if phase == 2 && self.nonbasic[j] == -1 {
+                    continue;
+                }
+                let better = match s {
+                    None => true,
+                    Some(s) if bland => {
+                        self.d[x][j] < -EPS
+                            && (self.d[x][s] >= -EPS || self.nonbasic[j] < self.nonbasic[s])
+                    }
+                    Some(s) => {
+                        self.d[x][j] < self.d[x][s]
+                            || (self.d[x][j] == self.d[x][s] && self.nonbasic[j] < self.nonbasic[s])
+                    }
+                };
+                if better {
+                    s = Some(j);
+                }
+            }
+            let s = match s {
+                Some(s) if self.d[x][s] <= -EPS => s,
+                _ => return true,
+            };
+            let mut r: Option<usize> = None;
+            for i in 0..self.m {
+                if self.d[i][s] < EPS {
+                    continue;
+                }
+                let better = match r {
+                    None => true,
+                    Some(r) => {
+                        let ratio = self.d[i][self.n + 1] / self.d[i][s];
+                        let best = self.d[r][self.n + 1] / self.d[r][s];
+                        ratio < best - EPS || (ratio < best + EPS && self.basic[i] < self.basic[r])
+                    }
+                };
+                if better {
+                    r = Some(i);
+                }
+            }
+            let r = match r {
+                Some(r) => r,
+                None => return false,
+            };
+            let before = self.d[x][self.n + 1];
+            self.pivot(r, s);
+            if (self.d[x][self.n + 1] - before).abs() < EPS {
+                degenerate += 1;
+            } else {
+                degenerate = 0;
+            }
+        }
+    }
+
+    fn solve(mut self) -> LpResult {
+        let (m, n) = (self.m, self.n);
+        if m > 0 {
+            let mut r = 0;
+            for i in 1..m {
+                if self.d[i][n + 1] < self.d[r][n + 1] {
+                    r = i;
+                }
+            }
+            if self.d[r][n + 1] < -EPS {
+                self.pivot(r, n);
+                if !self.simplex(1) || self.d[m + 1][n + 1] < -EPS {
+                    return LpResult::Infeasible;
+                }
+                // drive the artificial variable out of the basis
+                for i in 0..m {
+                    if self.basic[i] == -1 {
+                        let mut s = 0;
+                        for j in 1..=n {
+                            if self.d[i][j] < self.d[i][s]
+                                || (self.d[i][j] == self.d[i][s]
+                                    && self.nonbasic[j] < self.nonbasic[s])
+                            {
+                                s = j;
+                            }
+                        }
+                        self.pivot(i, s);
+                    }
+                }
+            }
+        }
+        if !self.simplex(2) {
+            return LpResult::Unbounded;
+        }
+        let mut x = vec![0.; n];
+        for i in 0..m {
+            if self.basic[i] >= 0 && (self.basic[i] as usize) < n {
+                x[self.basic[i] as usize] = self.d[i][n + 1];
+            }
+        }
+        LpResult::Optimal(self.d[m][n + 1], x)
+    }
+}
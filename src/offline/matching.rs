@@ -0,0 +1,292 @@
+use std::collections::VecDeque;
+
+const INF: usize = usize::MAX;
+
+/// Maximum cardinality matching of a bipartite graph by Hopcroft–Karp,
+/// in O(E * sqrt(V)).
+///
+/// `online_adjacency_list[v]` lists the offline nodes adjacent to the
+/// online node `v`, exactly like `Bigraph::v_adjacency_list`.
+/// Returns the matching size and, for every online node,
+/// the offline node it is matched to.
+pub fn hopcroft_karp(
+    offline_size: usize,
+    online_adjacency_list: &[Vec<usize>],
+) -> (usize, Vec<Option<usize>>) {
+    let online_size = online_adjacency_list.len();
+    let mut online_pair: Vec<Option<usize>> = vec![None; online_size];
+    let mut offline_pair: Vec<Option<usize>> = vec![None; offline_size];
+    let mut dist = vec![INF; online_size];
+    let mut size = 0;
+
+    while bfs(
+        online_adjacency_list,
+        &online_pair,
+        &offline_pair,
+        &mut dist,
+    ) {
+        let mut next_edge = vec![0; online_size];
+        for v in 0..online_size {
+            if online_pair[v].is_none()
+                && dfs(
+                    v,
+                    online_adjacency_list,
+                    &mut online_pair,
+                    &mut offline_pair,
+                    &mut dist,
+                    &mut next_edge,
+                )
+            {
+                size += 1;
+            }
+        }
+    }
+    (size, online_pair)
+}
+
+/// Maximum weight matching of a bipartite graph in which only the offline
+/// nodes are weighted, in O(V * E).
+///
+/// The sets of offline nodes which can be matched together form a matroid,
+/// so it greedily tries to match the offline nodes by decreasing weight,
+/// each through an augmenting path which keeps the earlier ones matched.
+/// Returns the matching weight and, for every online node,
+/// the offline node it is matched to.
+pub fn vertex_weighted_matching(
+    offline_weights: &[f64],
+    online_adjacency_list: &[Vec<usize>],
+) -> (f64, Vec<Option<usize>>) {
+    let offline_size = offline_weights.len();
+    let mut offline_adjacency_list = vec![vec![]; offline_size];
+    for (v, online_adj) in online_adjacency_list.iter().enumerate() {
+        for &u in online_adj.iter() {
+            offline_adjacency_list[u].push(v);
+        }
+    }
+    let mut order: Vec<usize> = (0..offline_size).collect();
+    order.sort_by(|&a, &b| offline_weights[b].total_cmp(&offline_weights[a]));
+    let mut online_pair: Vec<Option<usize>> = vec![None; online_adjacency_list.len()];
+    let mut weight = 0.;
+    for u in order {
+        if augment_from_offline(u, &offline_adjacency_list, &mut online_pair) {
+            weight += offline_weights[u];
+        }
+    }
+    (weight, online_pair)
+}
+
+// Searches an augmenting path from the free offline node `root`, which
+// rematches the offline nodes on the path but never unmatches one.
+fn augment_from_offline(
+    root: usize,
+    offline_adjacency_list: &[Vec<usize>],
+    online_pair: &mut [Option<usize>],
+) -> bool {
+    let mut visited = vec![false; online_pair.len()];
+    // the offline nodes of the path, with the next online node to try
+    let mut stack = vec![(root, 0)];
+    while let Some((u, next)) = stack.last_mut() {
+        let adj = &offline_adjacency_list[*u];
+        if *next == adj.len() {
+            stack.pop();
+            continue;
+        }
+        let v = adj[*next];
+        *next += 1;
+        if visited[v] {
+            continue;
+        }
+        visited[v] = true;
+        match online_pair[v] {
+            None => {
+                // every offline node of the path takes the online node
+                // it was trying
+                for &(u, next) in stack.iter() {
+                    online_pair[offline_adjacency_list[u][next - 1]] = Some(u);
+                }
+                return true;
+            }
+            Some(w) => stack.push((w, 0)),
+        }
+    }
+    false
+}
+
+/// Maximum weight matching of a bipartite graph with edge weights by the
+/// Hungarian algorithm, in O(V^3).
+///
+/// `online_adjacency_list[v]` lists the offline nodes adjacent to the
+/// online node `v` with the weight of the edge, exactly like
+/// `WBigraph::v_adjacency_list`. Edges of non-positive weight are never
+/// worth matching, and of parallel edges only the heaviest is. Returns the matching weight and, for every online node,
+/// the offline node it is matched to.
+pub fn max_weight_matching(
+    offline_size: usize,
+    online_adjacency_list: &[Vec<(usize, f64)>],
+) -> (f64, Vec<Option<usize>>) {
+    let online_size = online_adjacency_list.len();
+    // a square assignment problem minimizing the negated weights,
+    // missing edges weigh 0 and are dropped at the end
+    let size = usize::max(online_size, offline_size);
+    let mut cost = vec![vec![0.; size]; size];
+    for (v, online_adj) in online_adjacency_list.iter().enumerate() {
+        for &(u, weight) in online_adj.iter() {
+            cost[v][u] = f64::min(cost[v][u], -weight);
+        }
+    }
+    // potentials of the rows and columns, with 1-based columns and the
+    // column 0 as the root of the alternating tree
+    let mut row_potential = vec![0.; size + 1];
+    let mut column_potential = vec![0.; size + 1];
+    let mut column_row = vec![0; size + 1];
+    let mut way = vec![0; size + 1];
+    for row in 1..=size {
+        column_row[0] = row;
+        let mut column = 0;
+        let mut min_slack = vec![f64::INFINITY; size + 1];
+        let mut used = vec![false; size + 1];
+        loop {
+            used[column] = true;
+            let current_row = column_row[column];
+            let mut delta = f64::INFINITY;
+            let mut next_column = 0;
+            for j in 1..=size {
+                if used[j] {
+                    continue;
+                }
+                let slack =
+                    cost[current_row - 1][j - 1] - row_potential[current_row] - column_potential[j];
+                if slack < min_slack[j] {
+                    min_slack[j] = slack;
+                    way[j] = column;
+                }
+                if min_slack[j] < delta {
+                    delta = min_slack[j];
+                    next_column = j;
+                }
+            }
+            for j in 0..=size {
+                if used[j] {
+                    row_potential[column_row[j]] += delta;
+                    column_potential[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+            column = next_column;
+            if column_row[column] == 0 {
+                break;
+            }
+        }
+        // flip the alternating path back to the root
+        while column != 0 {
+            let previous = way[column];
+            column_row[column] = column_row[previous];
+            column = previous;
+        }
+    }
+
+    let mut online_pair = vec![None; online_size];
+    let mut weight = 0.;
+    for u in 0..offline_size {
+        let v = column_row[u + 1] - 1;
+        if v >= online_size {
+            continue;
+        }
+        let edge_weight = heaviest(&online_adjacency_list[v], u);
+        if edge_weight > 0. {
+            online_pair[v] = Some(u);
+            weight += edge_weight;
+        }
+    }
+    (weight, online_pair)
+}
+
+/// The weight of the heaviest edge to the offline node `u` in an
+/// adjacency list like `WBigraph::v_adjacency_list`, 0 if there is none.
+pub fn heaviest(online_adjacent: &[(usize, f64)], u: usize) -> f64 {
+    online_adjacent
+        .iter()
+        .filter(|&&(w, _)| w == u)
+        .fold(0., |heaviest, &(_, weight)| f64::max(heaviest, weight))
+}
+
+// Layers the online nodes by their alternating distance from a free
+// online node, returns whether some free offline node is reachable.
+fn bfs(
+    online_adjacency_list: &[Vec<usize>],
+    online_pair: &[Option<usize>],
+    offline_pair: &[Option<usize>],
+    dist: &mut [usize],
+) -> bool {
+    let mut queue = VecDeque::new();
+    for v in 0..online_adjacency_list.len() {
+        if online_pair[v].is_none() {
+            dist[v] = 0;
+            queue.push_back(v);
+        } else {
+            dist[v] = INF;
+        }
+    }
+    let mut found = false;
+    while let Some(v) = queue.pop_front() {
+        for &u in online_adjacency_list[v].iter() {
+            match offline_pair[u] {
+                None => found = true,
+                Some(w) => {
+                    if dist[w] == INF {
+                        dist[w] = dist[v] + 1;
+                        queue.push_back(w);
+                    }
+                }
+            }
+        }
+    }
+    found
+}
+
+// Searches an augmenting path from the free online node `root` along the
+// bfs layers. The search is iterative so that long paths on big instances
+// can't overflow the stack.
+fn dfs(
+    root: usize,
+    online_adjacency_list: &[Vec<usize>],
+    online_pair: &mut [Option<usize>],
+    offline_pair: &mut [Option<usize>],
+    dist: &mut [usize],
+    next_edge: &mut [usize],
+) -> bool {
+    let mut stack = vec![root];
+    while let Some(&v) = stack.last() {
+        let adj = &online_adjacency_list[v];
+        if next_edge[v] == adj.len() {
+            // dead end, never visit this node again in the current phase
+            dist[v] = INF;
+            stack.pop();
+            if let Some(&parent) = stack.last() {
+                next_edge[parent] += 1;
+            }
+            continue;
+        }
+        let u = adj[next_edge[v]];
+        match offline_pair[u] {
+            None => {
+                // augment along the whole stack
+                for &w in stack.iter() {
+                    let u = online_adjacency_list[w][next_edge[w]];
+                    online_pair[w] = Some(u);
+                    offline_pair[u] = Some(w);
+                }
+                return true;
+            }
+            Some(w) => {
+                if dist[w] != INF && dist[w] == dist[v] + 1 {
+                    stack.push(w);
+                } else {
+                    next_edge[v] += 1;
+                }
+            }
+        }
+    }
+    false
+}
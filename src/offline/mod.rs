@@ -0,0 +1,5 @@
+pub mod allocation;
+pub mod flow;
+pub mod lp;
+pub mod matching;
+pub mod reusable;
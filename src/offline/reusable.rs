@@ -0,0 +1,130 @@
+use super::lp::LinearProgram;
+
+// The time-expanded program of matching with reusable offline nodes:
+// the online node arriving at time t may take an adjacent offline node u,
+// which is then unavailable until time t + duration. So for every offline
+// node any window of `duration` consecutive arrivals holds at most one of
+// its matches, and it is enough to take the windows opening at its edges.
+fn time_expanded(
+    offline_size: usize,
+    online_adjacency_list: &[Vec<usize>],
+    duration: usize,
+) -> (LinearProgram, Vec<(usize, usize)>) {
+    let mut edges = Vec::new();
+    for (t, adj) in online_adjacency_list.iter().enumerate() {
+        for &u in adj.iter() {
+            edges.push((t, u));
+        }
+    }
+    let mut lp = LinearProgram::new(vec![1.; edges.len()]);
+    let mut online_rows = vec![vec![]; online_adjacency_list.len()];
+    let mut offline_edges = vec![vec![]; offline_size];
+    for (k, &(t, u)) in edges.iter().enumerate() {
+        online_rows[t].push((k, 1.));
+        offline_edges[u].push((t, k));
+    }
+    for row in online_rows {
+        lp.add_constraint(row, 1.);
+    }
+    if duration > 1 {
+        for u_edges in offline_edges.iter() {
+            for (i, &(start, _)) in u_edges.iter().enumerate() {
+                let window: Vec<(usize, f64)> = u_edges[i..]
+                    .iter()
+                    .take_while(|&&(t, _)| t < start + duration)
+                    .map(|&(_, k)| (k, 1.))
+                    .collect();
+                if window.len() > 1 {
+                    lp.add_constraint(window, 1.);
+                }
+            }
+        }
+    }
+    (lp, edges)
+}
+
+/// The LP relaxation of the time-expanded program, an upper bound
+/// of the clairvoyant offline optimum.
+pub fn reusable_matching_lp(
+    offline_size: usize,
+    online_adjacency_list: &[Vec<usize>],
+    duration: usize,
+) -> f64 {
+    time_expanded(offline_size, online_adjacency_list, duration)
+        .0
+        .maximize()
+        .value()
+}
+
+/// The clairvoyant offline optimum of matching with reusable offline nodes,
+/// where a node matched at time t is available again at time t + duration.
+/// Returns the number of matches and the offline node each online node
+/// takes. It runs a branch and bound, only use it on small instances.
+pub fn reusable_matching(
+    offline_size: usize,
+    online_adjacency_list: &[Vec<usize>],
+    duration: usize,
+) -> (usize, Vec<Option<usize>>) {
+    let (lp, edges) = time_expanded(offline_size, online_adjacency_list, duration);
+    let result = lp.maximize_integral(&(0..edges.len()).collect::<Vec<_>>());
+    let mut matching = vec![None; online_adjacency_list.len()];
+    if let super::lp::LpResult::Optimal(_, ref x) = result {
+        for (k, &(t, u)) in edges.iter().enumerate() {
+            if x[k] > 0.5 {
+                matching[t] = Some(u);
+            }
+        }
+    }
+    (result.value().round() as usize, matching)
+}
+
+/// The LP relaxation of matching with reusable offline nodes whose usage
+/// durations are random: `survival[u][s]` is the probability that u is
+/// still in use s arrivals after being matched (1 for s = 0, 0 for s at
+/// least `survival[u].len()`), and u has `capacities[u]` units. With x_tu
+/// the probability that the online node arriving at time t takes u,
+///     maximize sum(x_tu)
+///     s.t. sum_u x_tu <= 1 for every online t,
+///          sum_{t' <= t} x_t'u * survival[u][t - t'] <= c_u for every edge (t, u),
+/// i.e. at most c_u units of u are in use in expectation at every time.
+/// With fixed durations and unit capacities it is `reusable_matching_lp`,
+/// in general it upper bounds the expected optimum of a clairvoyant
+/// offline algorithm which learns every duration when it is drawn.
+pub fn stochastic_reusable_matching_lp(
+    online_adjacency_list: &[Vec<usize>],
+    survival: &[Vec<f64>],
+    capacities: &[usize],
+) -> f64 {
+    let mut edges = Vec::new();
+    for (t, adj) in online_adjacency_list.iter().enumerate() {
+        for &u in adj.iter() {
+            edges.push((t, u));
+        }
+    }
+    let mut lp = LinearProgram::new(vec![1.; edges.len()]);
+    let mut online_rows = vec![vec![]; online_adjacency_list.len()];
+    let mut offline_edges = vec![vec![]; survival.len()];
+    for (k, &(t, u)) in edges.iter().enumerate() {
+        online_rows[t].push((k, 1.));
+        offline_edges[u].push((t, k));
+    }
+    for row in online_rows {
+        lp.add_constraint(row, 1.);
+    }
+    for (u, u_edges) in offline_edges.iter().enumerate() {
+        for (i, &(t, _)) in u_edges.iter().enumerate() {
+            let row: Vec<(usize, f64)> = u_edges[..=i]
+                .iter()
+                .filter(|&&(start, _)| t - start < survival[u].len())
+                .map(|&(start, k)| (k, survival[u][t - start]))
+                .filter(|&(_, q)| q > 0.)
+                .collect();
+            // implied by the online constraints unless it has more than
+            // c_u terms
+            if row.len() > capacities[u] {
+                lp.add_constraint(row, capacities[u] as f64);
+            }
+        }
+    }
+    lp.maximize().value()
+}
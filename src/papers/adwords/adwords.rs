@@ -1,17 +1,42 @@
-use crate::{papers::algorithm::algorithm::OnlineAlgorithm, weightedbigraph::WBigraph};
+use crate::{
+    error::Error,
+    offline::allocation::{budgeted_allocation, budgeted_allocation_lp},
+    papers::algorithm::algorithm::OnlineAlgorithm,
+    papers::instance::{random_order, ArrivalIter, Instance, Simulate},
+    papers::outcome::{Decision, OfflineState, Outcome},
+    papers::validation::{check_adjacent, Violation},
+    weightedbigraph::WBigraph,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::borrow::Borrow;
 
 type OfflineInfo<Weight> = Vec<Weight>;
 
 impl<Key, Weight> WBigraph<Key, Weight> {
-    pub fn into_adwords(self: Self, budget: Vec<Weight>) -> AdversarialAdwords<Key, Weight> {
-        assert_eq!(
-            budget.len(),
-            self.u_nodes.len() // format!("budget = {:?},\n  graph = {:?}\n", budget, self)
-        );
-        AdversarialAdwords {
+    /// Panics if there isn't exactly one budget for every offline node,
+    /// `try_into_adwords` returns an `Error` instead.
+    pub fn into_adwords(self, budget: Vec<Weight>) -> AdversarialAdwords<Key, Weight>
+    where
+        Key: std::fmt::Debug,
+    {
+        self.try_into_adwords(budget)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_into_adwords(
+        self,
+        budget: Vec<Weight>,
+    ) -> Result<AdversarialAdwords<Key, Weight>, Error<Key>> {
+        if budget.len() != self.u_nodes.len() {
+            return Err(Error::BudgetLengthMismatch {
+                offline_size: self.u_nodes.len(),
+                budgets: budget.len(),
+            });
+        }
+        Ok(AdversarialAdwords {
             online_budget: budget,
             weighted_bigraph: self,
-        }
+        })
     }
 }
 
@@ -21,47 +46,293 @@ pub struct AdversarialAdwords<Key, Weight> {
     pub weighted_bigraph: WBigraph<Key, Weight>,
 }
 
+pub type AdversarialAdwordsIter<'a, Weight> = ArrivalIter<'a, (usize, Weight)>;
+
+impl<Key, Weight: Copy + Into<f64>> Instance for AdversarialAdwords<Key, Weight> {
+    type Key = Key;
+    type AdjType = (usize, Weight);
+    type OfflineInfo = OfflineInfo<Weight>;
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.weighted_bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.weighted_bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> OfflineInfo<Weight> {
+        self.online_budget.clone()
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, (usize, Weight)> {
+        self.iter()
+    }
+
+    fn OPT(&self) -> f64 {
+        AdversarialAdwords::OPT(self)
+    }
+}
+
+impl<Key, Weight, Alg> Simulate<Alg> for AdversarialAdwords<Key, Weight>
+where
+    Key: Copy,
+    Weight: Copy + Into<f64>,
+    Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>,
+{
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation> {
+        self.run::<Alg>(rng)
+    }
+}
+
 impl<'a, Key, Weight: Clone> AdversarialAdwords<Key, Weight> {
-    pub fn iter(self: &'a Self) -> AdversarialAdwordsIter<'a, Weight> {
-        AdversarialAdwordsIter {
-            online_adjacency_list: &self.weighted_bigraph.v_adjacency_list,
-            online_index: 0,
-        }
+    pub fn iter(&'a self) -> AdversarialAdwordsIter<'a, Weight> {
+        ArrivalIter::new(&self.weighted_bigraph.v_adjacency_list)
     }
+}
 
+impl<Key, Weight: Copy + Into<f64>> AdversarialAdwords<Key, Weight> {
+    /// Panics if the algorithm makes an invalid decision,
+    /// `run` reports it as a `Violation` instead.
     #[allow(non_snake_case)]
-    pub fn OPT(self: &Self) -> f64 {
-        // temporary unsound
-        self.weighted_bigraph.u_nodes.len() as f64
+    pub fn ALG<Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>>(&self) -> f64 {
+        self.ALG_with_rng::<Alg>(&mut StdRng::from_entropy())
     }
 
+    /// Same as `ALG`, but the algorithm draws its randomness from `rng`.
     #[allow(non_snake_case)]
-    pub fn ALG<Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>>(
-        self: &Self
+    pub fn ALG_with_rng<Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>>(
+        &self,
+        rng: &mut StdRng,
     ) -> f64 {
-        let mut alg = Alg::init(self.online_budget.clone());
-        for online_adj in self.iter() {
-            let _alg_choose = alg.dispatch(online_adj);
+        let mut alg =
+            Alg::init_with_rng(self.online_budget.clone(), StdRng::seed_from_u64(rng.gen()));
+        if let Err(violation) = self.drive(&mut alg, |_, _| {}) {
+            panic!("{}", violation);
         }
         alg.alg_output()
     }
+
+    /// The average ALG over `precision` trials in the random-order model,
+    /// every trial presents the online nodes in a fresh uniformly random
+    /// order drawn from `rng`. Panics if the algorithm makes an invalid
+    /// decision, `random_order_run` reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn random_order_ALG<Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>>(
+        &self,
+        precision: usize,
+        rng: &mut StdRng,
+    ) -> f64 {
+        let mut alg_sum = 0.;
+        for _ in 0..precision {
+            let order = random_order(self.weighted_bigraph.v_nodes.len(), rng);
+            let mut alg =
+                Alg::init_with_rng(self.online_budget.clone(), StdRng::seed_from_u64(rng.gen()));
+            if let Err(violation) = self.drive_in_order(&order, &mut alg, |_, _| {}) {
+                panic!("{}", violation);
+            }
+            alg_sum += alg.alg_output();
+        }
+        alg_sum / precision as f64
+    }
+
+    fn drive<Alg, F>(&self, alg: &mut Alg, observe: F) -> Result<(), Violation>
+    where
+        Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>,
+        F: FnMut(usize, Option<(usize, f64)>),
+    {
+        drive(&self.online_budget, self.iter(), alg, observe)
+    }
+
+    // Same as `drive`, but the online node `order[t]` arrives at time t,
+    // `observe` and the violation still get the index of the online node
+    // in the graph.
+    fn drive_in_order<Alg, F>(
+        &self,
+        order: &[usize],
+        alg: &mut Alg,
+        mut observe: F,
+    ) -> Result<(), Violation>
+    where
+        Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>,
+        F: FnMut(usize, Option<(usize, f64)>),
+    {
+        let online_adjacency_list = &self.weighted_bigraph.v_adjacency_list;
+        drive(
+            &self.online_budget,
+            order.iter().map(|&v| &online_adjacency_list[v]),
+            alg,
+            |t, alg_choose| observe(order[t], alg_choose),
+        )
+        .map_err(|violation| violation.in_order(order))
+    }
 }
 
-pub struct AdversarialAdwordsIter<'a, Weight> {
-    pub online_adjacency_list: &'a Vec<Vec<(usize, Weight)>>,
-    pub online_index: usize,
+/// Runs the algorithm on online nodes which aren't known in advance,
+/// every item of `arrivals` is the list of bids of the next online node.
+/// Returns the first decision which breaks the budgets as a `Violation`.
+#[allow(non_snake_case)]
+pub fn stream_ALG<Alg, Weight, I>(
+    budgets: Vec<Weight>,
+    arrivals: I,
+    rng: &mut StdRng,
+) -> Result<f64, Violation>
+where
+    Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>,
+    Weight: Copy + Into<f64>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<(usize, Weight)>>,
+{
+    let mut alg = Alg::init_with_rng(budgets.clone(), StdRng::seed_from_u64(rng.gen()));
+    drive(&budgets, arrivals, &mut alg, |_, _| {})?;
+    Ok(alg.alg_output())
 }
 
-impl<'a, Weight> Iterator for AdversarialAdwordsIter<'a, Weight> {
-    type Item = &'a Vec<(usize, Weight)>;
+// Feeds the online nodes to `alg` in arrival order, checks that every
+// choice is an adjacent advertiser whose budget isn't exhausted yet and
+// reports it with its bid to `observe` along with the index of the
+// online node.
+fn drive<Alg, Weight, I, F>(
+    budgets: &[Weight],
+    arrivals: I,
+    alg: &mut Alg,
+    mut observe: F,
+) -> Result<(), Violation>
+where
+    Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>,
+    Weight: Copy + Into<f64>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<(usize, Weight)>>,
+    F: FnMut(usize, Option<(usize, f64)>),
+{
+    let budgets: Vec<f64> = budgets.iter().map(|&b| b.into()).collect();
+    let mut spent = vec![0.; budgets.len()];
+    for (v, online_adj) in arrivals.into_iter().enumerate() {
+        let online_adj = online_adj.borrow();
+        let alg_choose = match alg.dispatch(online_adj) {
+            Some(u) => {
+                let &(_, bid) = check_adjacent(v, u, budgets.len(), online_adj, |adj| adj.0)?;
+                // a little slack, algorithms may track their loads
+                // with a different rounding
+                if spent[u] >= budgets[u] * (1. - 1e-9) {
+                    return Err(Violation::BudgetExhausted {
+                        online: v,
+                        offline: u,
+                    });
+                }
+                spent[u] += bid.into();
+                Some((u, bid.into()))
+            }
+            None => None,
+        };
+        observe(v, alg_choose);
+    }
+    Ok(())
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.online_index == self.online_adjacency_list.len() {
-            None
-        } else {
-            let t = Some(&self.online_adjacency_list[self.online_index]);
-            self.online_index += 1;
-            t
-        }
+impl<Key: Copy, Weight: Copy + Into<f64>> AdversarialAdwords<Key, Weight> {
+    /// Runs the algorithm once and reports every decision with its bid and
+    /// the budget every advertiser spent, keyed by the keys of the graph,
+    /// or the first decision which breaks the budgets.
+    pub fn run<Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation> {
+        let order: Vec<usize> = (0..self.weighted_bigraph.v_nodes.len()).collect();
+        self.run_in_order::<Alg>(&order, rng)
+    }
+
+    /// Same as `run` in the random-order model, the decisions are
+    /// reported in the random order the online nodes arrived in.
+    pub fn random_order_run<Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation> {
+        let order = random_order(self.weighted_bigraph.v_nodes.len(), rng);
+        self.run_in_order::<Alg>(&order, rng)
+    }
+
+    fn run_in_order<Alg: OnlineAlgorithm<(usize, Weight), OfflineInfo<Weight>>>(
+        &self,
+        order: &[usize],
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation> {
+        let graph = &self.weighted_bigraph;
+        let mut alg =
+            Alg::init_with_rng(self.online_budget.clone(), StdRng::seed_from_u64(rng.gen()));
+        let budgets: Vec<f64> = self.online_budget.iter().map(|&b| b.into()).collect();
+        let mut decisions = Vec::with_capacity(graph.v_nodes.len());
+        let mut loads = vec![0.; budgets.len()];
+        self.drive_in_order(order, &mut alg, |v, alg_choose| {
+            if let Some((u, bid)) = alg_choose {
+                loads[u] = f64::min(loads[u] + bid, budgets[u]);
+            }
+            decisions.push(Decision {
+                online: graph.v_nodes[v],
+                offline: alg_choose.map(|(u, _)| graph.u_nodes[u]),
+                weight: alg_choose.map(|(_, bid)| bid),
+                success: None,
+            });
+        })?;
+        let offline = graph
+            .u_nodes
+            .iter()
+            .enumerate()
+            .map(|(u, &key)| OfflineState {
+                key,
+                load: loads[u],
+                available: loads[u] < budgets[u],
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective: alg.alg_output(),
+        })
+    }
+}
+
+impl<Key, Weight: Copy + Into<f64>> AdversarialAdwords<Key, Weight> {
+    fn budgets_and_bids(&self) -> (Vec<f64>, Vec<Vec<(usize, f64)>>) {
+        let budgets = self.online_budget.iter().map(|&b| b.into()).collect();
+        let bids = self
+            .weighted_bigraph
+            .v_adjacency_list
+            .iter()
+            .map(|adj| adj.iter().map(|&(u, bid)| (u, bid.into())).collect())
+            .collect();
+        (budgets, bids)
+    }
+
+    /// The benchmark, always `integral_OPT`. It is exponential in the
+    /// worst case, on large instances compare against `fractional_OPT`.
+    #[allow(non_snake_case)]
+    pub fn OPT(&self) -> f64 {
+        self.integral_OPT()
+    }
+
+    /// The integral offline optimum, every advertiser earns at most its budget.
+    /// It is computed by branch and bound, which is exponential in the
+    /// worst case.
+    #[allow(non_snake_case)]
+    pub fn integral_OPT(&self) -> f64 {
+        self.opt_allocation().0
+    }
+
+    /// The allocation behind `integral_OPT`, returns the revenue and the offline
+    /// node every online node is assigned to.
+    pub fn opt_allocation(&self) -> (f64, Vec<Option<usize>>) {
+        let (budgets, bids) = self.budgets_and_bids();
+        budgeted_allocation(&budgets, &bids)
+    }
+
+    /// The LP relaxation of the offline optimum, at least `OPT`, so the
+    /// ratio against it is a lower bound of the ratio against `OPT`. It is
+    /// solved by the dense simplex of `offline::lp`, see there for the
+    /// size it can handle.
+    #[allow(non_snake_case)]
+    pub fn fractional_OPT(&self) -> f64 {
+        let (budgets, bids) = self.budgets_and_bids();
+        budgeted_allocation_lp(&budgets, &bids)
     }
 }
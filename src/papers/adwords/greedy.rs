@@ -32,7 +32,7 @@ where
         }
     }
 
-    fn dispatch(self: &mut Self, online_adjacent: &Vec<(usize, Weight)>) -> Option<usize> {
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, Weight)>) -> Option<usize> {
         let available_offline_nodes = get_available_offline_nodes_in_weighted_onlineadj(
             &self.offline_nodes_available,
             online_adjacent,
@@ -59,7 +59,7 @@ where
         }
     }
 
-    fn alg_output(self: Self) -> f64 {
+    fn alg_output(self) -> f64 {
         self.offline_nodes_loads
             .iter()
             .map(|&x| x.into())
@@ -78,9 +78,7 @@ pub mod example {
             edges.push(((1, i), 1.));
         }
         let wbigraph = WBigraph::from_edges(&edges);
-        let mut budgets = Vec::new();
-        budgets.push(n as f64);
-        budgets.push(n as f64);
+        let budgets = vec![n as f64, n as f64];
         wbigraph.into_adwords(budgets)
     }
 }
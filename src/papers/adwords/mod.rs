@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod adwords;
 pub mod msvv05;
 pub mod greedy;
@@ -38,7 +38,7 @@ where
         }
     }
 
-    fn dispatch(self: &mut Self, online_adjacent: &Vec<(usize, Weight)>) -> Option<usize> {
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, Weight)>) -> Option<usize> {
         let available_offline_nodes = get_available_offline_nodes_in_weighted_onlineadj(
             &self.offline_nodes_available,
             online_adjacent,
@@ -72,7 +72,7 @@ where
         }
     }
 
-    fn alg_output(self: Self) -> f64 {
+    fn alg_output(self) -> f64 {
         let l = self.offline_nodes_available.len();
         assert_eq!(self.offline_nodes_budgets.len(), l);
         assert_eq!(self.offline_nodes_fraction.len(), l);
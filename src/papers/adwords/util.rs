@@ -1,6 +1,6 @@
 pub fn get_available_offline_nodes_in_weighted_onlineadj<Weight: Copy>(
-    offline_nodes_available: &Vec<bool>,
-    online_adjacent: &Vec<(usize, Weight)>,
+    offline_nodes_available: &[bool],
+    online_adjacent: &[(usize, Weight)],
 ) -> Vec<(usize, Weight)> {
     let mut vec = Vec::with_capacity(online_adjacent.len());
     for (offline_node, w) in online_adjacent.iter() {
@@ -1,8 +1,13 @@
 use self::algorithm::OnlineAlgorithm;
 use crate::bigraph::Bigraph;
+use crate::papers::instance::{random_order, ArrivalIter, Instance, Simulate};
+use crate::papers::outcome::{Decision, OfflineState, Outcome};
+use crate::papers::validation::{check_adjacent, Violation};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::borrow::Borrow;
 
 impl<Key> Bigraph<Key> {
-    pub fn into_online(self: Self) -> OnlineAdversarialBigraph<Key> {
+    pub fn into_online(self) -> OnlineAdversarialBigraph<Key> {
         let offline_size = self.u_nodes.len();
         let mut vec = Vec::with_capacity(offline_size);
         vec.resize(offline_size, true);
@@ -14,59 +19,267 @@ pub struct OnlineAdversarialBigraph<Key> {
     bigraph: Bigraph<Key>,
 }
 
+pub type OnlineAdversarialBigraphIter<'a> = ArrivalIter<'a, usize>;
+
+impl<Key> Instance for OnlineAdversarialBigraph<Key> {
+    type Key = Key;
+    type AdjType = usize;
+    type OfflineInfo = usize;
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> usize {
+        self.bigraph.u_nodes.len()
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, usize> {
+        self.iter()
+    }
+
+    fn OPT(&self) -> f64 {
+        OnlineAdversarialBigraph::OPT(self)
+    }
+}
+
+impl<Key, Alg> Simulate<Alg> for OnlineAdversarialBigraph<Key>
+where
+    Key: Copy,
+    Alg: OnlineAlgorithm<usize, usize>,
+{
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation> {
+        self.run::<Alg>(rng)
+    }
+}
+
 impl<'a, Key> OnlineAdversarialBigraph<Key> {
-    pub fn iter(self: &'a Self) -> OnlineAdversarialBigraphIter<'a> {
-        OnlineAdversarialBigraphIter {
-            online_adjacency_list: &self.bigraph.v_adjacency_list,
-            online_index: 0,
-        }
+    pub fn iter(&'a self) -> OnlineAdversarialBigraphIter<'a> {
+        ArrivalIter::new(&self.bigraph.v_adjacency_list)
     }
 
+    /// The offline optimum, i.e. the size of the maximum matching, see
+    /// `Bigraph::maximum_matching`.
     #[allow(non_snake_case)]
-    pub fn OPT(self: &Self) -> f64 {
-        // temporary unsound
-        self.bigraph.u_nodes.len() as f64
+    pub fn OPT(&self) -> f64 {
+        self.bigraph.maximum_matching().0 as f64
     }
 
+    /// Panics if the algorithm makes an invalid decision,
+    /// `run` reports it as a `Violation` instead.
     #[allow(non_snake_case)]
-    pub fn ALG<Alg: OnlineAlgorithm<usize, usize>>(self: &Self) -> f64 {
-        let mut alg = Alg::init(self.bigraph.u_nodes.len());
-        for online_adj in self.iter() {
-            // println!("{:?}", online_adj);
-            let _alg_choose = alg.dispatch(online_adj);
+    pub fn ALG<Alg: OnlineAlgorithm<usize, usize>>(&self) -> f64 {
+        self.ALG_with_rng::<Alg>(&mut StdRng::from_entropy())
+    }
+
+    /// Same as `ALG`, but the algorithm draws its randomness from `rng`,
+    /// so the same seed replays the same run.
+    #[allow(non_snake_case)]
+    pub fn ALG_with_rng<Alg: OnlineAlgorithm<usize, usize>>(&self, rng: &mut StdRng) -> f64 {
+        let mut alg =
+            Alg::init_with_rng(self.bigraph.u_nodes.len(), StdRng::seed_from_u64(rng.gen()));
+        if let Err(violation) = self.drive(&mut alg, |_, _| {}) {
+            panic!("{}", violation);
         }
         alg.alg_output()
     }
-}
 
-pub struct OnlineAdversarialBigraphIter<'a> {
-    online_adjacency_list: &'a Vec<Vec<usize>>,
-    online_index: usize,
+    /// The average ALG over `precision` trials in the random-order model,
+    /// every trial presents the online nodes in a fresh uniformly random
+    /// order drawn from `rng`. Panics if the algorithm makes an invalid
+    /// decision, `random_order_run` reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn random_order_ALG<Alg: OnlineAlgorithm<usize, usize>>(
+        &self,
+        precision: usize,
+        rng: &mut StdRng,
+    ) -> f64 {
+        let mut alg_sum = 0.;
+        for _ in 0..precision {
+            let order = random_order(self.bigraph.v_nodes.len(), rng);
+            let mut alg =
+                Alg::init_with_rng(self.bigraph.u_nodes.len(), StdRng::seed_from_u64(rng.gen()));
+            if let Err(violation) = self.drive_in_order(&order, &mut alg, |_, _| {}) {
+                panic!("{}", violation);
+            }
+            alg_sum += alg.alg_output();
+        }
+        alg_sum / precision as f64
+    }
+
+    fn drive<Alg, F>(&self, alg: &mut Alg, observe: F) -> Result<(), Violation>
+    where
+        Alg: OnlineAlgorithm<usize, usize>,
+        F: FnMut(usize, Option<usize>),
+    {
+        drive(self.bigraph.u_nodes.len(), self.iter(), alg, observe)
+    }
+
+    // Same as `drive`, but the online node `order[t]` arrives at time t,
+    // `observe` and the violation still get the index of the online node
+    // in the graph.
+    fn drive_in_order<Alg, F>(
+        &self,
+        order: &[usize],
+        alg: &mut Alg,
+        mut observe: F,
+    ) -> Result<(), Violation>
+    where
+        Alg: OnlineAlgorithm<usize, usize>,
+        F: FnMut(usize, Option<usize>),
+    {
+        let online_adjacency_list = &self.bigraph.v_adjacency_list;
+        drive(
+            self.bigraph.u_nodes.len(),
+            order.iter().map(|&v| &online_adjacency_list[v]),
+            alg,
+            |t, alg_choose| observe(order[t], alg_choose),
+        )
+        .map_err(|violation| violation.in_order(order))
+    }
 }
 
-impl<'a> Iterator for OnlineAdversarialBigraphIter<'a> {
-    type Item = &'a Vec<usize>;
+/// Runs the algorithm on online nodes which aren't known in advance,
+/// e.g. read from a trace or received from a channel. Every item of
+/// `arrivals` is the adjacency list of the next online node, it is
+/// dropped once the algorithm has made its decision.
+/// Returns the first invalid decision as a `Violation`.
+#[allow(non_snake_case)]
+pub fn stream_ALG<Alg, I>(
+    offline_size: usize,
+    arrivals: I,
+    rng: &mut StdRng,
+) -> Result<f64, Violation>
+where
+    Alg: OnlineAlgorithm<usize, usize>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<usize>>,
+{
+    let mut alg = Alg::init_with_rng(offline_size, StdRng::seed_from_u64(rng.gen()));
+    drive(offline_size, arrivals, &mut alg, |_, _| {})?;
+    Ok(alg.alg_output())
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.online_index == self.online_adjacency_list.len() {
-            None
-        } else {
-            let t = Some(&self.online_adjacency_list[self.online_index]);
-            self.online_index += 1;
-            t
+// Feeds the online nodes to `alg` in arrival order, checks that every
+// choice is an adjacent and still unmatched offline node and reports it
+// to `observe` along with the index of the online node. Models with
+// the same rules but richer offline information share it.
+pub(crate) fn drive<Alg, Info, I, F>(
+    offline_size: usize,
+    arrivals: I,
+    alg: &mut Alg,
+    mut observe: F,
+) -> Result<(), Violation>
+where
+    Alg: OnlineAlgorithm<usize, Info>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<usize>>,
+    F: FnMut(usize, Option<usize>),
+{
+    let mut matched = vec![false; offline_size];
+    for (v, online_adj) in arrivals.into_iter().enumerate() {
+        let online_adj = online_adj.borrow();
+        let alg_choose = alg.dispatch(online_adj);
+        if let Some(u) = alg_choose {
+            check_adjacent(v, u, offline_size, online_adj, |&u| u)?;
+            if matched[u] {
+                return Err(Violation::AlreadyMatched {
+                    online: v,
+                    offline: u,
+                });
+            }
+            matched[u] = true;
         }
+        observe(v, alg_choose);
     }
+    Ok(())
 }
 
+impl<Key: Copy> OnlineAdversarialBigraph<Key> {
+    /// Runs the algorithm once and reports every decision and the final
+    /// state of the offline nodes, keyed by the keys of the graph,
+    /// or the first decision which isn't a valid matching.
+    pub fn run<Alg: OnlineAlgorithm<usize, usize>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation> {
+        let order: Vec<usize> = (0..self.bigraph.v_nodes.len()).collect();
+        self.run_in_order::<Alg>(&order, rng)
+    }
+
+    /// Same as `run` in the random-order model, the decisions are
+    /// reported in the random order the online nodes arrived in.
+    pub fn random_order_run<Alg: OnlineAlgorithm<usize, usize>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation> {
+        let order = random_order(self.bigraph.v_nodes.len(), rng);
+        self.run_in_order::<Alg>(&order, rng)
+    }
+
+    fn run_in_order<Alg: OnlineAlgorithm<usize, usize>>(
+        &self,
+        order: &[usize],
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation> {
+        let v_nodes = &self.bigraph.v_nodes;
+        let u_nodes = &self.bigraph.u_nodes;
+        let mut alg = Alg::init_with_rng(u_nodes.len(), StdRng::seed_from_u64(rng.gen()));
+        let mut decisions = Vec::with_capacity(v_nodes.len());
+        let mut loads = vec![0.; u_nodes.len()];
+        self.drive_in_order(order, &mut alg, |v, alg_choose| {
+            if let Some(u) = alg_choose {
+                loads[u] += 1.;
+            }
+            decisions.push(Decision {
+                online: v_nodes[v],
+                offline: alg_choose.map(|u| u_nodes[u]),
+                weight: alg_choose.map(|_| 1.),
+                success: None,
+            });
+        })?;
+        let offline = u_nodes
+            .iter()
+            .zip(loads)
+            .map(|(&key, load)| OfflineState {
+                key,
+                load,
+                available: load == 0.,
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective: alg.alg_output(),
+        })
+    }
+}
+
+#[allow(clippy::module_inception)]
 pub mod algorithm {
+    use rand::rngs::StdRng;
+
     pub trait OnlineAlgorithm<AdjType, OfflineInfo>
     where
         Self: Sized,
     {
         fn init(offline_info: OfflineInfo) -> Self;
 
-        fn dispatch(self: &mut Self, online_adjacent: &Vec<AdjType>) -> Option<usize>;
+        /// Randomized algorithms must draw all their randomness from `rng`,
+        /// so that a run can be replayed bit-for-bit from its seed.
+        /// Deterministic algorithms don't need to override it.
+        fn init_with_rng(offline_info: OfflineInfo, rng: StdRng) -> Self {
+            let _ = rng;
+            Self::init(offline_info)
+        }
+
+        #[allow(clippy::ptr_arg)]
+        fn dispatch(&mut self, online_adjacent: &Vec<AdjType>) -> Option<usize>;
 
-        fn alg_output(self: Self) -> f64;
+        fn alg_output(self) -> f64;
     }
 }
@@ -0,0 +1,280 @@
+use crate::bigraph::Bigraph;
+use crate::error::Error;
+use crate::offline::flow::capacitated_matching;
+use crate::papers::instance::{ArrivalIter, Instance, SimulateFractional};
+use crate::papers::outcome::{FractionalOutcome, OfflineState};
+use crate::papers::validation::{check_adjacent, Violation};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::borrow::Borrow;
+
+use self::algorithm::FractionalAlgorithm;
+
+/// The capacity of every offline node.
+pub type OfflineInfo = Vec<usize>;
+
+// rounding slack of the fractions
+const EPS: f64 = 1e-9;
+
+impl<Key: Copy> Bigraph<Key> {
+    /// Offline node `u_nodes[i]` can be matched `capacities[i]` times.
+    /// Panics if there isn't exactly one capacity for every offline node,
+    /// `try_into_b_matching` returns an `Error` instead.
+    pub fn into_b_matching(self, capacities: Vec<usize>) -> BMatching<Key>
+    where
+        Key: std::fmt::Debug,
+    {
+        self.try_into_b_matching(capacities)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_into_b_matching(self, capacities: Vec<usize>) -> Result<BMatching<Key>, Error<Key>> {
+        if capacities.len() != self.u_nodes.len() {
+            return Err(Error::CapacityLengthMismatch {
+                offline_size: self.u_nodes.len(),
+                capacities: capacities.len(),
+            });
+        }
+        Ok(BMatching {
+            bigraph: self,
+            capacities,
+        })
+    }
+}
+
+/// Online b-matching: every online node brings one unit which may be split
+/// among its neighbours, every offline node takes at most its capacity.
+#[derive(Debug)]
+pub struct BMatching<Key> {
+    bigraph: Bigraph<Key>,
+    capacities: Vec<usize>,
+}
+
+pub mod algorithm {
+    use rand::rngs::StdRng;
+
+    /// An online algorithm which may split every online node among its
+    /// neighbours, an integral one puts the whole of it on one neighbour.
+    pub trait FractionalAlgorithm<AdjType, OfflineInfo>
+    where
+        Self: Sized,
+    {
+        fn init(offline_info: OfflineInfo) -> Self;
+
+        /// Randomized algorithms must draw all their randomness from `rng`,
+        /// deterministic algorithms don't need to override it.
+        fn init_with_rng(offline_info: OfflineInfo, rng: StdRng) -> Self {
+            let _ = rng;
+            Self::init(offline_info)
+        }
+
+        /// The fraction of the online node given to each offline node,
+        /// adding up to at most 1.
+        #[allow(clippy::ptr_arg)]
+        fn dispatch(&mut self, online_adjacent: &Vec<AdjType>) -> Vec<(usize, f64)>;
+
+        fn alg_output(self) -> f64;
+    }
+}
+
+impl<Key> Instance for BMatching<Key> {
+    type Key = Key;
+    type AdjType = usize;
+    type OfflineInfo = OfflineInfo;
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> OfflineInfo {
+        self.capacities.clone()
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, usize> {
+        ArrivalIter::new(&self.bigraph.v_adjacency_list)
+    }
+
+    fn OPT(&self) -> f64 {
+        BMatching::OPT(self)
+    }
+}
+
+impl<Key, Alg> SimulateFractional<Alg> for BMatching<Key>
+where
+    Key: Copy,
+    Alg: FractionalAlgorithm<usize, OfflineInfo>,
+{
+    fn simulate_fractional(&self, rng: &mut StdRng) -> Result<FractionalOutcome<Key>, Violation> {
+        self.run::<Alg>(rng)
+    }
+}
+
+impl<Key> BMatching<Key> {
+    pub fn capacities(&self) -> &Vec<usize> {
+        &self.capacities
+    }
+
+    /// The offline optimum, i.e. the size of the maximum b-matching, which
+    /// no fractional matching beats.
+    #[allow(non_snake_case)]
+    pub fn OPT(&self) -> f64 {
+        capacitated_matching(
+            &self
+                .capacities
+                .iter()
+                .map(|&b| b as f64)
+                .collect::<Vec<_>>(),
+            &vec![1.; self.bigraph.v_nodes.len()],
+            &self.bigraph.v_adjacency_list,
+        )
+        .0
+        .round()
+    }
+
+    /// Panics if the algorithm makes an invalid decision,
+    /// `run` reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn ALG<Alg: FractionalAlgorithm<usize, OfflineInfo>>(&self) -> f64 {
+        self.ALG_with_rng::<Alg>(&mut StdRng::from_entropy())
+    }
+
+    /// Same as `ALG`, but the algorithm draws its randomness from `rng`.
+    #[allow(non_snake_case)]
+    pub fn ALG_with_rng<Alg: FractionalAlgorithm<usize, OfflineInfo>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> f64 {
+        let mut alg = Alg::init_with_rng(self.capacities.clone(), StdRng::seed_from_u64(rng.gen()));
+        if let Err(violation) = self.drive(&mut alg, |_, _| {}) {
+            panic!("{}", violation);
+        }
+        alg.alg_output()
+    }
+
+    fn drive<Alg, F>(&self, alg: &mut Alg, observe: F) -> Result<(), Violation>
+    where
+        Alg: FractionalAlgorithm<usize, OfflineInfo>,
+        F: FnMut(usize, &Vec<(usize, f64)>),
+    {
+        drive(
+            &self.capacities,
+            &self.bigraph.v_adjacency_list,
+            alg,
+            observe,
+        )
+    }
+}
+
+impl<Key: Copy> BMatching<Key> {
+    /// Runs the algorithm once and reports how every online node was split
+    /// and the final load of the offline nodes, or the first decision which
+    /// isn't a valid fractional b-matching.
+    pub fn run<Alg: FractionalAlgorithm<usize, OfflineInfo>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<FractionalOutcome<Key>, Violation> {
+        let u_nodes = &self.bigraph.u_nodes;
+        let mut alg = Alg::init_with_rng(self.capacities.clone(), StdRng::seed_from_u64(rng.gen()));
+        let mut allocations = Vec::with_capacity(self.bigraph.v_nodes.len());
+        let mut loads = vec![0.; u_nodes.len()];
+        self.drive(&mut alg, |_, fractions| {
+            for &(u, fraction) in fractions.iter() {
+                loads[u] += fraction;
+            }
+            allocations.push(
+                fractions
+                    .iter()
+                    .map(|&(u, fraction)| (u_nodes[u], fraction))
+                    .collect(),
+            );
+        })?;
+        let offline = u_nodes
+            .iter()
+            .zip(loads)
+            .zip(self.capacities.iter())
+            .map(|((&key, load), &capacity)| OfflineState {
+                key,
+                load,
+                available: load < capacity as f64 - EPS,
+            })
+            .collect();
+        Ok(FractionalOutcome {
+            allocations,
+            offline,
+            objective: alg.alg_output(),
+        })
+    }
+}
+
+// Feeds the online nodes to `alg` in arrival order and checks that every
+// online node is split into non-negative fractions of adjacent offline
+// nodes adding up to at most 1, and that no offline node gets more than
+// its capacity.
+fn drive<Alg, I, F>(
+    capacities: &[usize],
+    arrivals: I,
+    alg: &mut Alg,
+    mut observe: F,
+) -> Result<(), Violation>
+where
+    Alg: FractionalAlgorithm<usize, OfflineInfo>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<usize>>,
+    F: FnMut(usize, &Vec<(usize, f64)>),
+{
+    let offline_size = capacities.len();
+    let mut loads = vec![0.; offline_size];
+    for (v, online_adj) in arrivals.into_iter().enumerate() {
+        let online_adj = online_adj.borrow();
+        let fractions = alg.dispatch(online_adj);
+        let mut total = 0.;
+        for &(u, fraction) in fractions.iter() {
+            check_adjacent(v, u, offline_size, online_adj, |&u| u)?;
+            if fraction < 0. {
+                return Err(Violation::InvalidFraction {
+                    online: v,
+                    total: fraction,
+                });
+            }
+            total += fraction;
+            loads[u] += fraction;
+            if loads[u] > capacities[u] as f64 + EPS {
+                return Err(Violation::CapacityExceeded {
+                    online: v,
+                    offline: u,
+                });
+            }
+        }
+        if total > 1. + EPS {
+            return Err(Violation::InvalidFraction { online: v, total });
+        }
+        observe(v, &fractions);
+    }
+    Ok(())
+}
+
+pub mod example {
+    use super::BMatching;
+    use crate::bigraph::Bigraph;
+
+    /// The upper triangular graph of `kvv90::example::ranking_worst_case`
+    /// blown up by b: n offline nodes of capacity b, and b online nodes of
+    /// every type i adjacent to the offline nodes i, ..., n - 1, arriving
+    /// type by type. The neighbours are listed from the last one, so that
+    /// ties are broken against the algorithm. A perfect b-matching exists,
+    /// while BALANCE and Water-Filling tend to 1 - 1/e as n and b grow.
+    pub fn upper_triangular(n: usize, b: usize) -> BMatching<usize> {
+        let mut edges = Vec::new();
+        for i in 0..n {
+            for copy in 0..b {
+                for u in (i..n).rev() {
+                    edges.push((u, i * b + copy));
+                }
+            }
+        }
+        Bigraph::from_edges(&edges).into_b_matching(vec![b; n])
+    }
+}
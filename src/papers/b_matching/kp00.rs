@@ -0,0 +1,38 @@
+use super::graph::{algorithm::FractionalAlgorithm, OfflineInfo};
+use crate::papers::util::least_used;
+
+/// BALANCE of Kalyanasundaram and Pruhs (TCS 2000): every online node is
+/// matched to the neighbour which has used the smallest fraction of its
+/// capacity, among those with some capacity left. Deterministic and
+/// 1 - 1/(1 + 1/b)^b-competitive, which tends to 1 - 1/e for large b.
+#[derive(Debug)]
+pub struct Balance {
+    capacities: Vec<usize>,
+    loads: Vec<usize>,
+    alg: usize,
+}
+
+impl FractionalAlgorithm<usize, OfflineInfo> for Balance {
+    fn init(capacities: OfflineInfo) -> Self {
+        Balance {
+            loads: vec![0; capacities.len()],
+            capacities,
+            alg: 0,
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Vec<(usize, f64)> {
+        match least_used(online_adjacent, &self.loads, &self.capacities) {
+            Some(u) => {
+                self.loads[u] += 1;
+                self.alg += 1;
+                vec![(u, 1.)]
+            }
+            None => vec![],
+        }
+    }
+
+    fn alg_output(self) -> f64 {
+        self.alg as f64
+    }
+}
@@ -0,0 +1,3 @@
+pub mod graph;
+pub mod kp00;
+pub mod water_filling;
@@ -0,0 +1,68 @@
+use super::graph::{algorithm::FractionalAlgorithm, OfflineInfo};
+
+/// The deterministic fractional Water-Filling algorithm, the limit of
+/// BALANCE as the capacities grow, (1 - 1/e)-competitive for any
+/// capacities.
+///
+/// Every online node is poured into its neighbours with the lowest level,
+/// the used fraction of their capacity, raising them evenly until the
+/// whole unit is poured or every neighbour is full.
+#[derive(Debug)]
+pub struct WaterFilling {
+    capacities: Vec<f64>,
+    levels: Vec<f64>,
+    alg: f64,
+}
+
+impl FractionalAlgorithm<usize, OfflineInfo> for WaterFilling {
+    fn init(capacities: OfflineInfo) -> Self {
+        WaterFilling {
+            levels: vec![0.; capacities.len()],
+            capacities: capacities.iter().map(|&b| b as f64).collect(),
+            alg: 0.,
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Vec<(usize, f64)> {
+        let mut neighbours: Vec<usize> = online_adjacent
+            .iter()
+            .copied()
+            .filter(|&u| self.capacities[u] > 0. && self.levels[u] < 1.)
+            .collect();
+        if neighbours.is_empty() {
+            return vec![];
+        }
+        neighbours.sort_by(|&a, &b| self.levels[a].total_cmp(&self.levels[b]));
+        // raise the water level over the first k neighbours until it
+        // reaches the next one, the full level 1, or the unit is poured
+        let mut water = 1.;
+        let mut level = self.levels[neighbours[0]];
+        let mut width = 0.;
+        for k in 0..neighbours.len() {
+            width += self.capacities[neighbours[k]];
+            let next = neighbours
+                .get(k + 1)
+                .map_or(1., |&u| f64::min(self.levels[u], 1.));
+            let rise = f64::min(next - level, water / width);
+            level += rise;
+            water -= rise * width;
+            if water <= 0. || level >= 1. {
+                break;
+            }
+        }
+        let mut fractions = Vec::new();
+        for &u in neighbours.iter() {
+            if self.levels[u] < level {
+                let fraction = (level - self.levels[u]) * self.capacities[u];
+                self.levels[u] = level;
+                self.alg += fraction;
+                fractions.push((u, fraction));
+            }
+        }
+        fractions
+    }
+
+    fn alg_output(self) -> f64 {
+        self.alg
+    }
+}
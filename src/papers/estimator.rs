@@ -0,0 +1,228 @@
+use crate::papers::instance::{ratio, Simulate, SimulateFractional};
+use crate::papers::validation::Violation;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::thread;
+
+/// Estimates the expected ALG and ALG / OPT of an algorithm by Monte-Carlo,
+/// running the trials of `Simulate::simulate` (or of
+/// `SimulateFractional::simulate_fractional`) on several threads.
+///
+/// Trial i is always seeded with the i-th seed drawn from `seed`, and the
+/// samples are summed in trial order, so the estimate only depends on the
+/// seed, not on the number of threads or how they are scheduled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimator {
+    /// The number of trials, or the most trials if `target_half_width` is set.
+    pub trials: usize,
+    pub threads: usize,
+    pub seed: u64,
+    /// The confidence level of the intervals, 0.95 by default.
+    pub confidence: f64,
+    /// Stops as soon as the confidence interval of ALG / OPT is at most this
+    /// wide on either side of the mean. Checked after every `batch` trials.
+    pub target_half_width: Option<f64>,
+    pub batch: usize,
+}
+
+/// The sample statistics of one quantity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub mean: f64,
+    /// The unbiased sample variance, 0 with less than two samples.
+    pub variance: f64,
+    pub standard_error: f64,
+    /// The normal-approximation confidence interval around the mean.
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Estimate {
+    pub fn half_width(&self) -> f64 {
+        (self.upper - self.lower) / 2.
+    }
+}
+
+/// What `Estimator::estimate` reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimation {
+    pub alg: Estimate,
+    pub ratio: Estimate,
+    pub opt: f64,
+    /// The number of trials actually run.
+    pub trials: usize,
+}
+
+impl Estimator {
+    /// `trials` trials seeded from `seed` on every available core, with 95%
+    /// confidence intervals and no early stopping.
+    pub fn new(trials: usize, seed: u64) -> Self {
+        Estimator {
+            trials,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            seed,
+            confidence: 0.95,
+            target_half_width: None,
+            batch: 1000,
+        }
+    }
+
+    /// Runs the trials of `Alg` on the instance and returns the statistics
+    /// of ALG and of ALG / OPT, or the violation of the first trial (in
+    /// trial order) in which the algorithm made an invalid decision.
+    pub fn estimate<Alg, Inst>(&self, instance: &Inst) -> Result<Estimation, Violation>
+    where
+        Inst: Simulate<Alg> + Sync,
+    {
+        self.estimate_objective(instance.OPT(), |rng| {
+            Ok(Simulate::<Alg>::simulate(instance, rng)?.objective)
+        })
+    }
+
+    /// Same as `estimate`, for a fractional algorithm.
+    pub fn estimate_fractional<Alg, Inst>(&self, instance: &Inst) -> Result<Estimation, Violation>
+    where
+        Inst: SimulateFractional<Alg> + Sync,
+    {
+        self.estimate_objective(instance.OPT(), |rng| {
+            Ok(SimulateFractional::<Alg>::simulate_fractional(instance, rng)?.objective)
+        })
+    }
+
+    // `trial` runs the algorithm once and returns its objective.
+    fn estimate_objective<F>(&self, opt: f64, trial: F) -> Result<Estimation, Violation>
+    where
+        F: Fn(&mut StdRng) -> Result<f64, Violation> + Sync,
+    {
+        let z = normal_quantile(0.5 + self.confidence / 2.);
+        let threads = self.threads.max(1);
+        let mut seeds = StdRng::seed_from_u64(self.seed);
+        let mut alg = Moments::default();
+        let mut ratios = Moments::default();
+        while alg.count < self.trials {
+            let batch = usize::min(self.batch.max(1), self.trials - alg.count);
+            let batch_seeds: Vec<u64> = (0..batch).map(|_| seeds.gen()).collect();
+            let chunk = batch.div_ceil(threads);
+            let results: Vec<Result<Vec<f64>, Violation>> = thread::scope(|scope| {
+                let handles: Vec<_> = batch_seeds
+                    .chunks(chunk)
+                    .map(|chunk_seeds| {
+                        let trial = &trial;
+                        scope.spawn(move || {
+                            chunk_seeds
+                                .iter()
+                                .map(|&seed| trial(&mut StdRng::seed_from_u64(seed)))
+                                .collect()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect()
+            });
+            for objectives in results {
+                for objective in objectives? {
+                    alg.push(objective);
+                    ratios.push(ratio(objective, opt));
+                }
+            }
+            if let Some(target) = self.target_half_width {
+                if ratios.count >= 2 && ratios.estimate(z).half_width() <= target {
+                    break;
+                }
+            }
+        }
+        Ok(Estimation {
+            alg: alg.estimate(z),
+            ratio: ratios.estimate(z),
+            opt,
+            trials: alg.count,
+        })
+    }
+}
+
+// Welford's running mean and sum of squared deviations.
+#[derive(Debug, Default)]
+struct Moments {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn estimate(&self, z: f64) -> Estimate {
+        let n = self.count as f64;
+        let variance = if self.count < 2 {
+            0.
+        } else {
+            self.m2 / (n - 1.)
+        };
+        let standard_error = (variance / n).sqrt();
+        Estimate {
+            mean: self.mean,
+            variance,
+            standard_error,
+            lower: self.mean - z * standard_error,
+            upper: self.mean + z * standard_error,
+        }
+    }
+}
+
+// The p-quantile of the standard normal distribution, by Acklam's rational
+// approximation (relative error below 1.2e-9).
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    };
+    if p <= 0. {
+        f64::NEG_INFINITY
+    } else if p >= 1. {
+        f64::INFINITY
+    } else if p < 0.02425 {
+        tail((-2. * p.ln()).sqrt())
+    } else if p > 1. - 0.02425 {
+        -tail((-2. * (1. - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    }
+}
@@ -0,0 +1,241 @@
+use crate::error::Error;
+use crate::offline::matching::{heaviest, max_weight_matching};
+use crate::papers::algorithm::algorithm::OnlineAlgorithm;
+use crate::papers::instance::{ArrivalIter, Instance, Simulate};
+use crate::papers::outcome::{Decision, OfflineState, Outcome};
+use crate::papers::validation::{check_adjacent, Violation};
+use crate::weightedbigraph::WBigraph;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::borrow::Borrow;
+
+pub type OfflineInfo = usize;
+
+impl<Key: Copy> WBigraph<Key, f64> {
+    /// Panics if an edge weight is negative or not finite,
+    /// `try_into_free_disposal` returns an `Error` instead.
+    pub fn into_free_disposal(self) -> FreeDisposal<Key>
+    where
+        Key: std::fmt::Debug,
+    {
+        self.try_into_free_disposal()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_into_free_disposal(self) -> Result<FreeDisposal<Key>, Error<Key>> {
+        for &((u, v), weight) in self.nodes_edges.iter() {
+            if !(weight >= 0. && weight.is_finite()) {
+                return Err(Error::InvalidEdgeWeight {
+                    offline: u,
+                    online: v,
+                    weight,
+                });
+            }
+        }
+        Ok(FreeDisposal {
+            weighted_bigraph: self,
+        })
+    }
+}
+
+/// Edge-weighted matching with free disposal (FKMMP09): an offline node
+/// may be assigned any number of online nodes, but only keeps its heaviest
+/// edge, the lighter ones are disposed for free.
+#[derive(Debug)]
+pub struct FreeDisposal<Key> {
+    pub weighted_bigraph: WBigraph<Key, f64>,
+}
+
+pub type FreeDisposalIter<'a> = ArrivalIter<'a, (usize, f64)>;
+
+impl<Key> Instance for FreeDisposal<Key> {
+    type Key = Key;
+    type AdjType = (usize, f64);
+    type OfflineInfo = OfflineInfo;
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.weighted_bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.weighted_bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> OfflineInfo {
+        self.weighted_bigraph.u_nodes.len()
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, (usize, f64)> {
+        self.iter()
+    }
+
+    fn OPT(&self) -> f64 {
+        FreeDisposal::OPT(self)
+    }
+}
+
+impl<Key, Alg> Simulate<Alg> for FreeDisposal<Key>
+where
+    Key: Copy,
+    Alg: OnlineAlgorithm<(usize, f64), OfflineInfo>,
+{
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation> {
+        self.run::<Alg>(rng)
+    }
+}
+
+impl<'a, Key> FreeDisposal<Key> {
+    pub fn iter(&'a self) -> FreeDisposalIter<'a> {
+        ArrivalIter::new(&self.weighted_bigraph.v_adjacency_list)
+    }
+
+    /// The offline optimum, i.e. the weight of the maximum weight matching,
+    /// disposing never helps an offline algorithm.
+    #[allow(non_snake_case)]
+    pub fn OPT(&self) -> f64 {
+        self.opt_matching().0
+    }
+
+    /// The matching behind `OPT`, returns its weight and the offline node
+    /// matched to every online node (in arrival order).
+    pub fn opt_matching(&self) -> (f64, Vec<Option<usize>>) {
+        max_weight_matching(
+            self.weighted_bigraph.u_nodes.len(),
+            &self.weighted_bigraph.v_adjacency_list,
+        )
+    }
+
+    /// Panics if the algorithm makes an invalid decision,
+    /// `run` reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn ALG<Alg: OnlineAlgorithm<(usize, f64), OfflineInfo>>(&self) -> f64 {
+        self.ALG_with_rng::<Alg>(&mut StdRng::from_entropy())
+    }
+
+    /// Same as `ALG`, but the algorithm draws its randomness from `rng`.
+    #[allow(non_snake_case)]
+    pub fn ALG_with_rng<Alg: OnlineAlgorithm<(usize, f64), OfflineInfo>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> f64 {
+        let mut alg = Alg::init_with_rng(
+            self.weighted_bigraph.u_nodes.len(),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+        if let Err(violation) = self.drive(&mut alg, |_, _| {}) {
+            panic!("{}", violation);
+        }
+        alg.alg_output()
+    }
+
+    fn drive<Alg, F>(&self, alg: &mut Alg, observe: F) -> Result<(), Violation>
+    where
+        Alg: OnlineAlgorithm<(usize, f64), OfflineInfo>,
+        F: FnMut(usize, Option<(usize, f64)>),
+    {
+        drive(
+            self.weighted_bigraph.u_nodes.len(),
+            self.iter(),
+            alg,
+            observe,
+        )
+    }
+}
+
+impl<Key: Copy> FreeDisposal<Key> {
+    /// Runs the algorithm once and reports every assignment with the
+    /// weight of its edge, and the heaviest edge every offline node keeps
+    /// as its load, or the first assignment to a non adjacent offline node.
+    pub fn run<Alg: OnlineAlgorithm<(usize, f64), OfflineInfo>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation> {
+        let v_nodes = &self.weighted_bigraph.v_nodes;
+        let u_nodes = &self.weighted_bigraph.u_nodes;
+        let mut alg = Alg::init_with_rng(u_nodes.len(), StdRng::seed_from_u64(rng.gen()));
+        let mut decisions = Vec::with_capacity(v_nodes.len());
+        let mut kept: Vec<Option<f64>> = vec![None; u_nodes.len()];
+        self.drive(&mut alg, |v, alg_choose| {
+            if let Some((u, weight)) = alg_choose {
+                kept[u] = Some(kept[u].map_or(weight, |w| f64::max(w, weight)));
+            }
+            decisions.push(Decision {
+                online: v_nodes[v],
+                offline: alg_choose.map(|(u, _)| u_nodes[u]),
+                weight: alg_choose.map(|(_, weight)| weight),
+                success: None,
+            });
+        })?;
+        let offline = u_nodes
+            .iter()
+            .zip(kept)
+            .map(|(&key, kept)| OfflineState {
+                key,
+                load: kept.unwrap_or(0.),
+                available: kept.is_none(),
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective: alg.alg_output(),
+        })
+    }
+}
+
+// Feeds the online nodes to `alg` in arrival order and checks that every
+// choice is an adjacent offline node, matched or not. Reports the choice
+// with the weight of its edge, the heaviest one if there are parallel
+// edges as in the OPT, to `observe` along with the index of the online node.
+fn drive<Alg, I, F>(
+    offline_size: usize,
+    arrivals: I,
+    alg: &mut Alg,
+    mut observe: F,
+) -> Result<(), Violation>
+where
+    Alg: OnlineAlgorithm<(usize, f64), OfflineInfo>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<(usize, f64)>>,
+    F: FnMut(usize, Option<(usize, f64)>),
+{
+    for (v, online_adj) in arrivals.into_iter().enumerate() {
+        let online_adj = online_adj.borrow();
+        let alg_choose = match alg.dispatch(online_adj) {
+            Some(u) => {
+                check_adjacent(v, u, offline_size, online_adj, |&(u, _)| u)?;
+                Some((u, heaviest(online_adj, u)))
+            }
+            None => None,
+        };
+        observe(v, alg_choose);
+    }
+    Ok(())
+}
+
+/// The heaviest edge every offline node keeps, the objective of the model,
+/// shared by the algorithms which track it.
+#[derive(Debug, Clone)]
+pub struct Kept {
+    weights: Vec<f64>,
+}
+
+impl Kept {
+    pub fn new(offline_size: usize) -> Self {
+        Kept {
+            weights: vec![0.; offline_size],
+        }
+    }
+
+    /// The weight `u` keeps so far, 0 if it isn't matched.
+    pub fn weight(&self, u: usize) -> f64 {
+        self.weights[u]
+    }
+
+    pub fn assign(&mut self, u: usize, weight: f64) {
+        self.weights[u] = f64::max(self.weights[u], weight);
+    }
+
+    pub fn total(&self) -> f64 {
+        self.weights.iter().sum()
+    }
+}
@@ -0,0 +1,36 @@
+use super::graph::{Kept, OfflineInfo};
+use crate::papers::algorithm::algorithm::OnlineAlgorithm;
+
+/// Assigns every online node to the neighbour whose kept edge it improves
+/// the most, 1/2-competitive with free disposal.
+#[derive(Debug)]
+pub struct Greedy {
+    kept: Kept,
+}
+
+impl OnlineAlgorithm<(usize, f64), OfflineInfo> for Greedy {
+    fn init(offline_size: OfflineInfo) -> Self {
+        Greedy {
+            kept: Kept::new(offline_size),
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, f64)>) -> Option<usize> {
+        let mut best = None;
+        let mut best_gain = 0.;
+        for &(u, weight) in online_adjacent.iter() {
+            let gain = weight - self.kept.weight(u);
+            if gain > best_gain {
+                best_gain = gain;
+                best = Some((u, weight));
+            }
+        }
+        let (u, weight) = best?;
+        self.kept.assign(u, weight);
+        Some(u)
+    }
+
+    fn alg_output(self) -> f64 {
+        self.kept.total()
+    }
+}
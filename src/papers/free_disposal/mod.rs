@@ -0,0 +1,4 @@
+pub mod graph;
+pub mod greedy;
+pub mod ocs;
+pub mod ocs_primal_dual;
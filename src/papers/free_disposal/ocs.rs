@@ -0,0 +1,65 @@
+use rand::{rngs::StdRng, Rng};
+
+/// The online correlated selection of Fahrbach, Huang, Tao and
+/// Zadimoghaddam (FOCS 2020). Every round brings a pair of elements and
+/// selects one of them, so that an element in k rounds is never selected
+/// with probability at most 2^(-k) (1 - 1/16)^(k - 1), instead of the
+/// 2^(-k) of independent coin flips.
+///
+/// Every round is a sender or a receiver with probability 1/2. A sender
+/// selects uniformly and points one of its two elements, uniformly, at the
+/// next round containing it. A receiver looks back along one of its two
+/// elements, uniformly, and if a sender points at it there, selects the
+/// opposite of the sender with respect to that element; otherwise it
+/// selects uniformly.
+#[derive(Debug)]
+pub struct OnlineCorrelatedSelection {
+    // the element selected by the sender pointing at the next round
+    // containing every element
+    pointed: Vec<Option<usize>>,
+}
+
+impl OnlineCorrelatedSelection {
+    pub fn new(elements: usize) -> Self {
+        OnlineCorrelatedSelection {
+            pointed: vec![None; elements],
+        }
+    }
+
+    /// A round with the element alone, which selects it. It still is the
+    /// next round of the element, so a sender pointing at the element is
+    /// used up and can't correlate with a later round.
+    pub fn select_alone(&mut self, element: usize) -> usize {
+        self.pointed[element] = None;
+        element
+    }
+
+    /// Selects one of the two distinct elements.
+    pub fn select(&mut self, pair: (usize, usize), rng: &mut StdRng) -> usize {
+        let (a, b) = pair;
+        // this round is the next one of both elements
+        let pointed = [self.pointed[a].take(), self.pointed[b].take()];
+        let elements = [a, b];
+        if rng.gen_bool(0.5) {
+            let selected = elements[rng.gen_range(0..2)];
+            self.pointed[elements[rng.gen_range(0..2)]] = Some(selected);
+            selected
+        } else {
+            let side = rng.gen_range(0..2);
+            match pointed[side] {
+                Some(sender_selected) if sender_selected == elements[side] => elements[1 - side],
+                Some(_) => elements[side],
+                None => elements[rng.gen_range(0..2)],
+            }
+        }
+    }
+}
+
+/// The probability that an element in k rounds of the OCS is never
+/// selected is at most 2^(-k) (1 - 1/16)^(k - 1).
+pub fn unselected(rounds: u32) -> f64 {
+    match rounds {
+        0 => 1.,
+        k => 0.5f64.powi(k as i32) * (15f64 / 16.).powi(k as i32 - 1),
+    }
+}
@@ -0,0 +1,143 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::graph::{Kept, OfflineInfo};
+use super::ocs::{unselected, OnlineCorrelatedSelection};
+use crate::papers::algorithm::algorithm::OnlineAlgorithm;
+
+/// A primal-dual heuristic for edge-weighted matching with free disposal
+/// on top of the online correlated selection of `ocs`. It greedily raises
+/// a lower bound of the expected weight kept and has no proven ratio.
+///
+/// For every offline node u and weight level w it counts the rounds k_u(w)
+/// which assigned u an edge of weight at least w, so that u keeps weight
+/// w with probability at least 1 - `unselected(k_u(w))`. Its dual is this
+/// lower bound integrated over the levels. Every online node either picks
+/// one neighbour deterministically, which counts as two rounds, or a pair
+/// of neighbours, whichever raises the dual the most.
+/// Both go through the OCS, a deterministic pick as a round of its own.
+#[derive(Debug)]
+pub struct OcsPrimalDualHeuristic {
+    // for every offline node the breakpoints (w, k) by increasing weight:
+    // the levels above the previous breakpoint up to w were assigned in
+    // k rounds, the levels above the last breakpoint in none
+    levels: Vec<Vec<(f64, u32)>>,
+    kept: Kept,
+    ocs: OnlineCorrelatedSelection,
+    rng: StdRng,
+}
+
+impl OcsPrimalDualHeuristic {
+    /// The dual, a lower bound of the expected weight kept so far.
+    pub fn dual(&self) -> f64 {
+        (0..self.levels.len())
+            .map(|u| {
+                let mut low = 0.;
+                let mut bound = 0.;
+                for &(high, rounds) in self.levels[u].iter() {
+                    bound += (high - low) * (1. - unselected(rounds));
+                    low = high;
+                }
+                bound
+            })
+            .sum()
+    }
+
+    // How much assigning `u` an edge of `weight` in `added` more rounds
+    // raises the dual of `u`.
+    fn gain(&self, u: usize, weight: f64, added: u32) -> f64 {
+        let mut gain = 0.;
+        let mut low = 0.;
+        for &(high, rounds) in self.levels[u].iter() {
+            if low >= weight {
+                return gain;
+            }
+            gain +=
+                (f64::min(high, weight) - low) * (unselected(rounds) - unselected(rounds + added));
+            low = high;
+        }
+        if low < weight {
+            gain += (weight - low) * (1. - unselected(added));
+        }
+        gain
+    }
+
+    fn assign(&mut self, u: usize, weight: f64, added: u32) {
+        let levels = &mut self.levels[u];
+        // split the level containing `weight`, or add one above the last
+        match levels.iter().position(|&(high, _)| high >= weight) {
+            Some(i) if levels[i].0 > weight => {
+                let rounds = levels[i].1;
+                levels.insert(i, (weight, rounds));
+            }
+            Some(_) => {}
+            None => levels.push((weight, 0)),
+        }
+        for (high, rounds) in levels.iter_mut() {
+            if *high > weight {
+                break;
+            }
+            *rounds += added;
+        }
+    }
+}
+
+impl OnlineAlgorithm<(usize, f64), OfflineInfo> for OcsPrimalDualHeuristic {
+    fn init(offline_size: OfflineInfo) -> Self {
+        Self::init_with_rng(offline_size, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(offline_size: OfflineInfo, rng: StdRng) -> Self {
+        OcsPrimalDualHeuristic {
+            levels: vec![vec![]; offline_size],
+            kept: Kept::new(offline_size),
+            ocs: OnlineCorrelatedSelection::new(offline_size),
+            rng,
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, f64)>) -> Option<usize> {
+        let mut deterministic: Option<(usize, f64, f64)> = None;
+        // the two neighbours with the largest randomized gains
+        let mut first: Option<(usize, f64, f64)> = None;
+        let mut second: Option<(usize, f64, f64)> = None;
+        for &(u, weight) in online_adjacent.iter() {
+            let gain = self.gain(u, weight, 2);
+            if deterministic.map_or(true, |(_, _, best)| gain > best) {
+                deterministic = Some((u, weight, gain));
+            }
+            let gain = self.gain(u, weight, 1);
+            if first.map_or(true, |(_, _, best)| gain > best) {
+                second = first;
+                first = Some((u, weight, gain));
+            } else if second.map_or(true, |(_, _, best)| gain > best) {
+                second = Some((u, weight, gain));
+            }
+        }
+        let (u, weight, deterministic_gain) = deterministic?;
+        let randomized_gain = match (first, second) {
+            (Some((_, _, a)), Some((_, _, b))) => a + b,
+            _ => 0.,
+        };
+        if deterministic_gain <= 0. && randomized_gain <= 0. {
+            return None;
+        }
+        if deterministic_gain >= randomized_gain {
+            self.assign(u, weight, 2);
+            let selected = self.ocs.select_alone(u);
+            self.kept.assign(selected, weight);
+            return Some(selected);
+        }
+        let (u1, weight1, _) = first.unwrap();
+        let (u2, weight2, _) = second.unwrap();
+        self.assign(u1, weight1, 1);
+        self.assign(u2, weight2, 1);
+        let selected = self.ocs.select((u1, u2), &mut self.rng);
+        let weight = if selected == u1 { weight1 } else { weight2 };
+        self.kept.assign(selected, weight);
+        Some(selected)
+    }
+
+    fn alg_output(self) -> f64 {
+        self.kept.total()
+    }
+}
@@ -0,0 +1,143 @@
+use crate::papers::outcome::{FractionalOutcome, Outcome};
+use crate::papers::validation::Violation;
+use rand::{rngs::StdRng, seq::SliceRandom};
+
+/// What every online model has in common: offline nodes known in advance,
+/// online nodes arriving one by one with their edges, and an offline
+/// benchmark. Tooling written against it (runners, ratio estimators,
+/// exporters) works for the unweighted, AdWords, reusable and
+/// stochastic-reward models alike.
+pub trait Instance {
+    type Key;
+    /// An entry of the adjacency list of an online node.
+    type AdjType;
+    /// What an algorithm of the model is initialized with.
+    type OfflineInfo;
+
+    fn offline_keys(&self) -> &Vec<Self::Key>;
+
+    /// In arrival order. In a model whose online nodes are sampled from
+    /// online types, like `known_iid`, these are the keys of the types and
+    /// every run names its online nodes by the key of their type.
+    fn online_keys(&self) -> &Vec<Self::Key>;
+
+    fn offline_info(&self) -> Self::OfflineInfo;
+
+    /// The adjacency lists of the online nodes, in arrival order,
+    /// or of the online types, see `online_keys`.
+    fn arrivals(&self) -> ArrivalIter<'_, Self::AdjType>;
+
+    /// The number of online nodes of a run, i.e. of the decisions of its
+    /// `Outcome`, which differs from the number of `online_keys` when the
+    /// online nodes are sampled from types.
+    fn online_size(&self) -> usize {
+        self.online_keys().len()
+    }
+
+    /// The benchmark the competitive ratio of the model is measured against.
+    #[allow(non_snake_case)]
+    fn OPT(&self) -> f64;
+}
+
+/// An instance on which the algorithm `Alg` can run.
+pub trait Simulate<Alg>: Instance {
+    /// Runs the algorithm once, a single trial in stochastic models,
+    /// see the `run` of every model.
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Self::Key>, Violation>;
+}
+
+/// An instance on which the fractional algorithm `Alg` can run, see
+/// `b_matching`.
+pub trait SimulateFractional<Alg>: Instance {
+    fn simulate_fractional(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<FractionalOutcome<Self::Key>, Violation>;
+}
+
+/// The average of ALG / OPT over `trials` runs.
+pub fn mean_ratio<Alg, Inst>(
+    instance: &Inst,
+    trials: usize,
+    rng: &mut StdRng,
+) -> Result<f64, Violation>
+where
+    Inst: Simulate<Alg>,
+{
+    mean_objective_ratio(instance.OPT(), trials, rng, |rng| {
+        Ok(instance.simulate(rng)?.objective)
+    })
+}
+
+/// Same as `mean_ratio`, for a fractional algorithm.
+pub fn mean_fractional_ratio<Alg, Inst>(
+    instance: &Inst,
+    trials: usize,
+    rng: &mut StdRng,
+) -> Result<f64, Violation>
+where
+    Inst: SimulateFractional<Alg>,
+{
+    mean_objective_ratio(instance.OPT(), trials, rng, |rng| {
+        Ok(instance.simulate_fractional(rng)?.objective)
+    })
+}
+
+fn mean_objective_ratio(
+    opt: f64,
+    trials: usize,
+    rng: &mut StdRng,
+    mut trial: impl FnMut(&mut StdRng) -> Result<f64, Violation>,
+) -> Result<f64, Violation> {
+    let mut alg_sum = 0.;
+    for _ in 0..trials {
+        alg_sum += trial(rng)?;
+    }
+    Ok(ratio(alg_sum / trials as f64, opt))
+}
+
+/// ALG / OPT, where an instance on which nothing can be matched counts as
+/// solved optimally: 0 / 0 is 1 rather than NaN.
+pub fn ratio(alg: f64, opt: f64) -> f64 {
+    if opt == 0. && alg == 0. {
+        1.
+    } else {
+        alg / opt
+    }
+}
+
+/// A uniformly random arrival order of `online_size` online nodes for the
+/// random-order model, `order[t]` is the online node arriving at time t.
+pub fn random_order(online_size: usize, rng: &mut StdRng) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..online_size).collect();
+    order.shuffle(rng);
+    order
+}
+
+/// Walks the adjacency lists of the online nodes in arrival order.
+pub struct ArrivalIter<'a, AdjType> {
+    pub online_adjacency_list: &'a Vec<Vec<AdjType>>,
+    pub online_index: usize,
+}
+
+impl<'a, AdjType> ArrivalIter<'a, AdjType> {
+    pub fn new(online_adjacency_list: &'a Vec<Vec<AdjType>>) -> Self {
+        ArrivalIter {
+            online_adjacency_list,
+            online_index: 0,
+        }
+    }
+}
+
+impl<'a, AdjType> Iterator for ArrivalIter<'a, AdjType> {
+    type Item = &'a Vec<AdjType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.online_index == self.online_adjacency_list.len() {
+            None
+        } else {
+            self.online_index += 1;
+            Some(&self.online_adjacency_list[self.online_index - 1])
+        }
+    }
+}
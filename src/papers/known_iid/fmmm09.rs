@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::graph::{DistinctTypes, KnownIIDAlgorithm, KnownIIDInfo};
+use crate::offline::flow::FlowNetwork;
+use crate::papers::algorithm::algorithm::OnlineAlgorithm;
+
+/// The "two suggested matchings" algorithm of Feldman, Mehta, Mirrokni and
+/// Muthukrishnan (FOCS 2009), 0.67-competitive when every type is expected
+/// to arrive an integral number of times.
+///
+/// A type expected to arrive r times is split into round(r) copies which
+/// are expected to arrive once, a type expected to arrive less than half a
+/// time still gets one copy. At `init` it computes a maximum flow in
+/// which every copy and every offline node has capacity 2 and every edge
+/// capacity 1, and splits its paths and cycles into a blue and a red
+/// matching. Every arrival picks a uniformly random copy of its type: the
+/// first arrival of a copy tries its blue partner, the second its red
+/// partner, later ones are dropped.
+pub struct FMMM09 {
+    offline_nodes_available: Vec<bool>,
+    type_of: HashMap<Vec<usize>, usize>,
+    copies: Vec<Vec<usize>>,
+    blue: Vec<Option<usize>>,
+    red: Vec<Option<usize>>,
+    arrived: Vec<usize>,
+    alg: usize,
+    rng: StdRng,
+}
+
+impl KnownIIDAlgorithm for FMMM09 {
+    type Info = KnownIIDInfo;
+}
+
+impl OnlineAlgorithm<usize, KnownIIDInfo> for FMMM09 {
+    fn init(info: KnownIIDInfo) -> Self {
+        Self::init_with_rng(info, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(info: KnownIIDInfo, rng: StdRng) -> Self {
+        let DistinctTypes {
+            type_adjacency_list,
+            expected_arrivals,
+            type_of,
+        } = info.distinct_types();
+        let mut copies = Vec::with_capacity(type_adjacency_list.len());
+        let mut copy_type = Vec::new();
+        for (t, rate) in expected_arrivals.iter().enumerate() {
+            let first = copy_type.len();
+            let count = if *rate > 0. {
+                usize::max(1, rate.round() as usize)
+            } else {
+                0
+            };
+            copy_type.resize(first + count, t);
+            copies.push((first..copy_type.len()).collect());
+        }
+        let (blue, red) = suggested_matchings(info.offline_size, &type_adjacency_list, &copy_type);
+        FMMM09 {
+            offline_nodes_available: vec![true; info.offline_size],
+            type_of,
+            copies,
+            arrived: vec![0; blue.len()],
+            blue,
+            red,
+            alg: 0,
+            rng,
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
+        let copies = &self.copies[*self.type_of.get(online_adjacent)?];
+        if copies.is_empty() {
+            return None;
+        }
+        let copy = copies[self.rng.gen_range(0..copies.len())];
+        self.arrived[copy] += 1;
+        let suggested = match self.arrived[copy] {
+            1 => self.blue[copy],
+            2 => self.red[copy],
+            _ => None,
+        }?;
+        if self.offline_nodes_available[suggested] {
+            self.offline_nodes_available[suggested] = false;
+            self.alg += 1;
+            Some(suggested)
+        } else {
+            None
+        }
+    }
+
+    fn alg_output(self) -> f64 {
+        self.alg as f64
+    }
+}
+
+// Returns the blue and the red partner of every copy.
+fn suggested_matchings(
+    offline_size: usize,
+    type_adjacency_list: &[Vec<usize>],
+    copy_type: &[usize],
+) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+    let copy_size = copy_type.len();
+    // source, copies, offline nodes, sink
+    let source = 0;
+    let sink = copy_size + offline_size + 1;
+    let mut network = FlowNetwork::new(sink + 1);
+    for c in 0..copy_size {
+        network.add_edge(source, 1 + c, 2.);
+    }
+    for u in 0..offline_size {
+        network.add_edge(1 + copy_size + u, sink, 2.);
+    }
+    let mut edges = Vec::new();
+    for (c, &t) in copy_type.iter().enumerate() {
+        for &u in type_adjacency_list[t].iter() {
+            edges.push((c, u, network.add_edge(1 + c, 1 + copy_size + u, 1.)));
+        }
+    }
+    network.max_flow(source, sink);
+
+    // the edges carrying flow form paths and even cycles,
+    // nodes are the copies followed by the offline nodes
+    let mut incident = vec![vec![]; copy_size + offline_size];
+    let mut used = Vec::new();
+    for &(c, u, e) in edges.iter() {
+        if network.flow(e) > 0.5 {
+            incident[c].push(used.len());
+            incident[copy_size + u].push(used.len());
+            used.push((c, u));
+        }
+    }
+    // colour them alternately, starting from the ends of the paths
+    let mut blue_edge = vec![None; used.len()];
+    let starts = (0..incident.len())
+        .filter(|&node| incident[node].len() == 1)
+        .chain(0..incident.len());
+    for start in starts {
+        let mut node = start;
+        let mut blue = true;
+        while let Some(&edge) = incident[node].iter().find(|&&e| blue_edge[e].is_none()) {
+            blue_edge[edge] = Some(blue);
+            blue = !blue;
+            let (c, u) = used[edge];
+            node = if node == c { copy_size + u } else { c };
+        }
+    }
+
+    let mut blue = vec![None; copy_size];
+    let mut red = vec![None; copy_size];
+    for (edge, &(c, u)) in used.iter().enumerate() {
+        if blue_edge[edge] == Some(true) {
+            blue[c] = Some(u);
+        } else {
+            red[c] = Some(u);
+        }
+    }
+    (blue, red)
+}
@@ -0,0 +1,388 @@
+use crate::bigraph::Bigraph;
+use crate::error::Error;
+use crate::offline::flow::capacitated_matching;
+use crate::offline::matching::hopcroft_karp;
+use crate::papers::algorithm::{algorithm::OnlineAlgorithm, drive};
+use crate::papers::instance::{ArrivalIter, Instance, Simulate};
+use crate::papers::kvv90;
+use crate::papers::outcome::{Decision, OfflineState, Outcome};
+use crate::papers::validation::Violation;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+
+impl<Key: Copy> Bigraph<Key> {
+    /// Reads the graph as a type graph: every v node is an online type,
+    /// each of the `arrivals` online nodes is of type v with probability
+    /// proportional to `rates[v]`.
+    /// Panics on invalid rates, `try_into_known_iid` returns an `Error` instead.
+    pub fn into_known_iid(self, rates: Vec<f64>, arrivals: usize) -> KnownIID<Key>
+    where
+        Key: std::fmt::Debug,
+    {
+        self.try_into_known_iid(rates, arrivals)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_into_known_iid(
+        self,
+        rates: Vec<f64>,
+        arrivals: usize,
+    ) -> Result<KnownIID<Key>, Error<Key>> {
+        // a type graph without types can't be sampled
+        if rates.len() != self.v_nodes.len() || rates.is_empty() {
+            return Err(Error::RateLengthMismatch {
+                types: self.v_nodes.len(),
+                rates: rates.len(),
+            });
+        }
+        for (v, &rate) in rates.iter().enumerate() {
+            if !(rate >= 0. && rate.is_finite()) {
+                return Err(Error::InvalidRate {
+                    online: self.v_nodes[v],
+                    rate,
+                });
+            }
+        }
+        let total: f64 = rates.iter().sum();
+        if total == 0. {
+            return Err(Error::AllRatesZero);
+        }
+        Ok(KnownIID {
+            distribution: rates.iter().map(|rate| rate / total).collect(),
+            bigraph: self,
+            arrivals,
+        })
+    }
+}
+
+/// The known-IID model of FMMM09: the type graph and the distribution of
+/// the types are known in advance, the online nodes are `arrivals`
+/// independent samples of it.
+#[derive(Debug)]
+pub struct KnownIID<Key> {
+    bigraph: Bigraph<Key>,
+    distribution: Vec<f64>,
+    arrivals: usize,
+}
+
+/// Everything the model reveals in advance, for the algorithms which
+/// exploit the distribution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownIIDInfo {
+    pub offline_size: usize,
+    /// The offline nodes adjacent to every online type.
+    pub type_adjacency_list: Vec<Vec<usize>>,
+    /// The probability that an online node is of every type.
+    pub distribution: Vec<f64>,
+    /// The number of online nodes.
+    pub arrivals: usize,
+}
+
+impl KnownIIDInfo {
+    /// The expected number of online nodes of every type.
+    pub fn expected_arrivals(&self) -> Vec<f64> {
+        self.distribution
+            .iter()
+            .map(|p| p * self.arrivals as f64)
+            .collect()
+    }
+
+    /// An algorithm only sees the neighbours of an online node, so the
+    /// types with the same adjacency list can't be told apart.
+    /// Merges them, see `DistinctTypes`.
+    pub fn distinct_types(&self) -> DistinctTypes {
+        let mut type_of = HashMap::new();
+        let mut type_adjacency_list = Vec::new();
+        let mut expected_arrivals = Vec::new();
+        for (online_adj, rate) in self
+            .type_adjacency_list
+            .iter()
+            .zip(self.expected_arrivals())
+        {
+            let t = *type_of.entry(online_adj.clone()).or_insert_with(|| {
+                type_adjacency_list.push(online_adj.clone());
+                expected_arrivals.push(0.);
+                type_adjacency_list.len() - 1
+            });
+            expected_arrivals[t] += rate;
+        }
+        DistinctTypes {
+            type_adjacency_list,
+            expected_arrivals,
+            type_of,
+        }
+    }
+}
+
+/// The online types with the same adjacency list merged into one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistinctTypes {
+    pub type_adjacency_list: Vec<Vec<usize>>,
+    /// The expected number of arrivals of every merged type.
+    pub expected_arrivals: Vec<f64>,
+    /// The merged type of every adjacency list.
+    pub type_of: HashMap<Vec<usize>, usize>,
+}
+
+/// How an algorithm's `OfflineInfo` is built from a known-IID instance,
+/// so that both the adversarial algorithms (which only get the number of
+/// offline nodes) and the IID-specific ones can run on it.
+pub trait FromKnownIID {
+    fn from_known_iid(info: &KnownIIDInfo) -> Self;
+}
+
+impl FromKnownIID for usize {
+    fn from_known_iid(info: &KnownIIDInfo) -> Self {
+        info.offline_size
+    }
+}
+
+impl FromKnownIID for KnownIIDInfo {
+    fn from_known_iid(info: &KnownIIDInfo) -> Self {
+        info.clone()
+    }
+}
+
+/// The algorithms which run on a known-IID instance through `Simulate`,
+/// with the `OfflineInfo` they are initialized with: the distribution for
+/// the IID-specific ones, the number of offline nodes for the adversarial
+/// ones, which don't know it.
+pub trait KnownIIDAlgorithm {
+    type Info: FromKnownIID;
+}
+
+impl KnownIIDAlgorithm for kvv90::Random {
+    type Info = usize;
+}
+
+impl KnownIIDAlgorithm for kvv90::Ranking {
+    type Info = usize;
+}
+
+/// The `online_keys` and `arrivals` of the instance are its online types,
+/// every run samples `online_size` online nodes of them, so the decisions
+/// of a run repeat the key of a type arriving several times. The
+/// benchmark is `fractional_OPT`, an upper bound of the expected OPT.
+impl<Key> Instance for KnownIID<Key> {
+    type Key = Key;
+    type AdjType = usize;
+    type OfflineInfo = KnownIIDInfo;
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> KnownIIDInfo {
+        self.info()
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, usize> {
+        ArrivalIter::new(&self.bigraph.v_adjacency_list)
+    }
+
+    fn online_size(&self) -> usize {
+        self.arrivals
+    }
+
+    fn OPT(&self) -> f64 {
+        self.fractional_OPT()
+    }
+}
+
+impl<Key, Alg> Simulate<Alg> for KnownIID<Key>
+where
+    Key: Copy,
+    Alg: KnownIIDAlgorithm + OnlineAlgorithm<usize, Alg::Info>,
+{
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation> {
+        self.run::<Alg, Alg::Info>(rng)
+    }
+}
+
+impl<Key> KnownIID<Key> {
+    pub fn info(&self) -> KnownIIDInfo {
+        KnownIIDInfo {
+            offline_size: self.bigraph.u_nodes.len(),
+            type_adjacency_list: self.bigraph.v_adjacency_list.clone(),
+            distribution: self.distribution.clone(),
+            arrivals: self.arrivals,
+        }
+    }
+
+    /// The types of the online nodes of one realization, in arrival order.
+    pub fn sample_arrivals(&self, rng: &mut StdRng) -> Vec<usize> {
+        let types = WeightedIndex::new(&self.distribution).unwrap();
+        (0..self.arrivals).map(|_| types.sample(rng)).collect()
+    }
+
+    /// The expected size of the maximum matching of a realization,
+    /// averaged over `precision` sampled realizations.
+    #[allow(non_snake_case)]
+    pub fn expected_OPT(&self, precision: usize) -> f64 {
+        self.expected_OPT_with_rng(precision, &mut StdRng::from_entropy())
+    }
+
+    /// Same as `expected_OPT`, but the realizations are sampled from `rng`.
+    #[allow(non_snake_case)]
+    pub fn expected_OPT_with_rng(&self, precision: usize, rng: &mut StdRng) -> f64 {
+        let type_adjacency_list = &self.bigraph.v_adjacency_list;
+        let mut opt_sum = 0.;
+        for _ in 0..precision {
+            let online_adjacency_list: Vec<Vec<usize>> = self
+                .sample_arrivals(rng)
+                .iter()
+                .map(|&t| type_adjacency_list[t].clone())
+                .collect();
+            opt_sum += hopcroft_karp(self.bigraph.u_nodes.len(), &online_adjacency_list).0 as f64;
+        }
+        opt_sum / precision as f64
+    }
+
+    /// The maximum matching of the type graph in which every type may be
+    /// used as many times as it is expected to arrive, an upper bound of
+    /// the expected OPT which the analysis of FMMM09 compares to.
+    #[allow(non_snake_case)]
+    pub fn fractional_OPT(&self) -> f64 {
+        let info = self.info();
+        capacitated_matching(
+            &vec![1.; info.offline_size],
+            &info.expected_arrivals(),
+            &info.type_adjacency_list,
+        )
+        .0
+    }
+
+    /// Runs the algorithm on one sampled realization.
+    /// Panics if the algorithm makes an invalid decision,
+    /// `run` reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn ALG<Alg, Info>(&self) -> f64
+    where
+        Alg: OnlineAlgorithm<usize, Info>,
+        Info: FromKnownIID,
+    {
+        self.ALG_with_rng::<Alg, Info>(&mut StdRng::from_entropy())
+    }
+
+    /// Same as `ALG`, but the realization and the algorithm draw their
+    /// randomness from `rng`.
+    #[allow(non_snake_case)]
+    pub fn ALG_with_rng<Alg, Info>(&self, rng: &mut StdRng) -> f64
+    where
+        Alg: OnlineAlgorithm<usize, Info>,
+        Info: FromKnownIID,
+    {
+        let types = self.sample_arrivals(rng);
+        let mut alg = Alg::init_with_rng(
+            Info::from_known_iid(&self.info()),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+        if let Err(violation) = self.drive(&types, &mut alg, |_, _| {}) {
+            panic!("{}", violation);
+        }
+        alg.alg_output()
+    }
+
+    /// The average ALG over `precision` sampled realizations.
+    #[allow(non_snake_case)]
+    pub fn expected_ALG_with_rng<Alg, Info>(&self, precision: usize, rng: &mut StdRng) -> f64
+    where
+        Alg: OnlineAlgorithm<usize, Info>,
+        Info: FromKnownIID,
+    {
+        let mut alg_sum = 0.;
+        for _ in 0..precision {
+            alg_sum += self.ALG_with_rng::<Alg, Info>(rng);
+        }
+        alg_sum / precision as f64
+    }
+
+    fn drive<Alg, Info, F>(
+        &self,
+        types: &[usize],
+        alg: &mut Alg,
+        observe: F,
+    ) -> Result<(), Violation>
+    where
+        Alg: OnlineAlgorithm<usize, Info>,
+        F: FnMut(usize, Option<usize>),
+    {
+        let type_adjacency_list = &self.bigraph.v_adjacency_list;
+        drive(
+            self.bigraph.u_nodes.len(),
+            types.iter().map(|&t| &type_adjacency_list[t]),
+            alg,
+            observe,
+        )
+    }
+}
+
+impl<Key: Copy> KnownIID<Key> {
+    /// Runs the algorithm on one sampled realization and reports every
+    /// decision, with the key of the type of the online node, and the final
+    /// state of the offline nodes, or the first decision which isn't a
+    /// valid matching.
+    pub fn run<Alg, Info>(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation>
+    where
+        Alg: OnlineAlgorithm<usize, Info>,
+        Info: FromKnownIID,
+    {
+        let v_nodes = &self.bigraph.v_nodes;
+        let u_nodes = &self.bigraph.u_nodes;
+        let types = self.sample_arrivals(rng);
+        let mut alg = Alg::init_with_rng(
+            Info::from_known_iid(&self.info()),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+        let mut decisions = Vec::with_capacity(types.len());
+        let mut loads = vec![0.; u_nodes.len()];
+        self.drive(&types, &mut alg, |v, alg_choose| {
+            if let Some(u) = alg_choose {
+                loads[u] += 1.;
+            }
+            decisions.push(Decision {
+                online: v_nodes[types[v]],
+                offline: alg_choose.map(|u| u_nodes[u]),
+                weight: alg_choose.map(|_| 1.),
+                success: None,
+            });
+        })?;
+        let offline = u_nodes
+            .iter()
+            .zip(loads)
+            .map(|(&key, load)| OfflineState {
+                key,
+                load,
+                available: load == 0.,
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective: alg.alg_output(),
+        })
+    }
+}
+
+pub mod example {
+    use super::KnownIID;
+    use crate::bigraph::Bigraph;
+
+    /// n offline and n online types, type i is adjacent to the offline
+    /// nodes i, ..., n - 1 as in `kvv90::example::ranking_worst_case`,
+    /// every type is equally likely and n online nodes arrive.
+    pub fn upper_triangular(n: usize) -> KnownIID<usize> {
+        let mut edges = Vec::new();
+        for v in 0..n {
+            for u in v..n {
+                edges.push((u, v));
+            }
+        }
+        Bigraph::from_edges(&edges).into_known_iid(vec![1.; n], n)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod fmmm09;
+pub mod graph;
+pub mod two_choice;
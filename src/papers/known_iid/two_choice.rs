@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::graph::{DistinctTypes, KnownIIDAlgorithm, KnownIIDInfo};
+use crate::offline::flow::FlowNetwork;
+use crate::papers::algorithm::algorithm::OnlineAlgorithm;
+
+/// A two-choice rounding of an LP over the type graph, in the spirit of
+/// Manshadi, Oveis Gharan and Saberi (SODA 2011) but without their LP
+/// constraints or their random lists, so it has no proven ratio. Unlike
+/// `FMMM09` it doesn't need integral arrival rates.
+///
+/// At `init` it takes a maximum flow over the type graph in which a type
+/// expected to arrive r times is matched at most r times and every edge
+/// carries at most 1 - e^(-r), the probability that the type arrives at
+/// all. Every arrival of a type suggests a neighbour with probability its
+/// flow / r, and none with the remaining probability. If the suggested
+/// neighbour is already matched it tries a second one, drawn likewise
+/// among the other neighbours, and otherwise gives up.
+pub struct TwoChoiceLpRounding {
+    offline_nodes_available: Vec<bool>,
+    type_of: HashMap<Vec<usize>, usize>,
+    // the neighbours of every type followed by None, for the probability
+    // that no neighbour is suggested, with the probability of every choice
+    choices: Vec<Vec<Option<usize>>>,
+    weights: Vec<Vec<f64>>,
+    alg: usize,
+    rng: StdRng,
+}
+
+impl KnownIIDAlgorithm for TwoChoiceLpRounding {
+    type Info = KnownIIDInfo;
+}
+
+impl OnlineAlgorithm<usize, KnownIIDInfo> for TwoChoiceLpRounding {
+    fn init(info: KnownIIDInfo) -> Self {
+        Self::init_with_rng(info, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(info: KnownIIDInfo, rng: StdRng) -> Self {
+        let DistinctTypes {
+            type_adjacency_list,
+            expected_arrivals,
+            type_of,
+        } = info.distinct_types();
+        let flows = lp_solution(info.offline_size, &type_adjacency_list, &expected_arrivals);
+        let mut choices = Vec::with_capacity(type_adjacency_list.len());
+        let mut type_weights = Vec::with_capacity(type_adjacency_list.len());
+        for (t, online_adj) in type_adjacency_list.iter().enumerate() {
+            // a type which never arrives has no flow
+            let rate = f64::max(expected_arrivals[t], f64::MIN_POSITIVE);
+            let mut weights: Vec<f64> = flows[t].iter().map(|f| f / rate).collect();
+            let suggested: f64 = weights.iter().sum();
+            weights.push(f64::max(1. - suggested, 0.));
+            let mut type_choices: Vec<Option<usize>> =
+                online_adj.iter().map(|&u| Some(u)).collect();
+            type_choices.push(None);
+            choices.push(type_choices);
+            type_weights.push(weights);
+        }
+        TwoChoiceLpRounding {
+            offline_nodes_available: vec![true; info.offline_size],
+            type_of,
+            choices,
+            weights: type_weights,
+            alg: 0,
+            rng,
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
+        let t = *self.type_of.get(online_adjacent)?;
+        let mut weights = self.weights[t].clone();
+        for _ in 0..2 {
+            let choice = WeightedIndex::new(&weights).ok()?.sample(&mut self.rng);
+            let u = self.choices[t][choice]?;
+            if self.offline_nodes_available[u] {
+                self.offline_nodes_available[u] = false;
+                self.alg += 1;
+                return Some(u);
+            }
+            // the second choice is another neighbour
+            weights[choice] = 0.;
+            *weights.last_mut().unwrap() = 0.;
+        }
+        None
+    }
+
+    fn alg_output(self) -> f64 {
+        self.alg as f64
+    }
+}
+
+// The LP value of every edge, in the layout of `type_adjacency_list`.
+fn lp_solution(
+    offline_size: usize,
+    type_adjacency_list: &[Vec<usize>],
+    expected_arrivals: &[f64],
+) -> Vec<Vec<f64>> {
+    let type_size = type_adjacency_list.len();
+    // source, types, offline nodes, sink
+    let source = 0;
+    let sink = type_size + offline_size + 1;
+    let mut network = FlowNetwork::new(sink + 1);
+    for (t, &rate) in expected_arrivals.iter().enumerate() {
+        network.add_edge(source, 1 + t, rate);
+    }
+    for u in 0..offline_size {
+        network.add_edge(1 + type_size + u, sink, 1.);
+    }
+    let edges: Vec<Vec<usize>> = type_adjacency_list
+        .iter()
+        .enumerate()
+        .map(|(t, online_adj)| {
+            let arrives = 1. - f64::exp(-expected_arrivals[t]);
+            online_adj
+                .iter()
+                .map(|&u| network.add_edge(1 + t, 1 + type_size + u, arrives))
+                .collect()
+        })
+        .collect();
+    network.max_flow(source, sink);
+    edges
+        .iter()
+        .map(|type_edges| type_edges.iter().map(|&e| network.flow(e)).collect())
+        .collect()
+}
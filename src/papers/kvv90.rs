@@ -1,26 +1,34 @@
 use crate::papers::util;
-use rand::{distributions::Uniform, thread_rng, Rng};
+use rand::{distributions::Uniform, rngs::StdRng, Rng, SeedableRng};
 
 use super::algorithm::algorithm::OnlineAlgorithm;
+use super::algorithm::{drive, OnlineAdversarialBigraph};
+use super::instance::Instance;
 
 type OfflineInfo = usize;
 
 pub struct Random {
     offline_nodes_available: Vec<bool>,
     pub alg: usize,
+    rng: StdRng,
 }
 
 impl OnlineAlgorithm<usize, OfflineInfo> for Random {
     fn init(offline_size: usize) -> Self {
+        Self::init_with_rng(offline_size, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(offline_size: usize, rng: StdRng) -> Self {
         let mut vec = Vec::with_capacity(offline_size);
         vec.resize(offline_size, true);
         Random {
             offline_nodes_available: vec,
             alg: 0,
+            rng,
         }
     }
 
-    fn dispatch(self: &mut Self, online_adjacent: &Vec<usize>) -> Option<usize> {
+    fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
         let available_offline_nodes = util::get_available_offline_nodes_in_onlineadj(
             &self.offline_nodes_available,
             online_adjacent,
@@ -28,8 +36,9 @@ impl OnlineAlgorithm<usize, OfflineInfo> for Random {
         if available_offline_nodes.is_empty() {
             None
         } else {
-            let mut rng = thread_rng();
-            let index: usize = rng.sample(Uniform::new(0, available_offline_nodes.len()));
+            let index: usize = self
+                .rng
+                .sample(Uniform::new(0, available_offline_nodes.len()));
             self.alg += 1;
             self.offline_nodes_available[available_offline_nodes[index]] = false;
             Some(available_offline_nodes[index])
@@ -38,7 +47,7 @@ impl OnlineAlgorithm<usize, OfflineInfo> for Random {
 
     // This should drop / move all the algotithm cause after output
     // It can't be used anymore.
-    fn alg_output(self: Self) -> f64 {
+    fn alg_output(self) -> f64 {
         self.alg as f64
     }
 }
@@ -49,8 +58,24 @@ pub struct Ranking {
     alg: usize,
 }
 
+impl Ranking {
+    /// Ranking with a fixed rank of every offline node instead of a random
+    /// one, the offline node with the lowest rank is preferred.
+    pub fn with_ranks(offline_nodes_rank: Vec<i32>) -> Self {
+        Ranking {
+            offline_nodes_available: vec![true; offline_nodes_rank.len()],
+            offline_nodes_rank,
+            alg: 0,
+        }
+    }
+}
+
 impl OnlineAlgorithm<usize, OfflineInfo> for Ranking {
     fn init(offline_size: usize) -> Self {
+        Self::init_with_rng(offline_size, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(offline_size: usize, mut rng: StdRng) -> Self {
         use rand::seq::SliceRandom;
         let mut off_available = Vec::with_capacity(offline_size);
         off_available.resize(offline_size, true);
@@ -58,7 +83,7 @@ impl OnlineAlgorithm<usize, OfflineInfo> for Ranking {
         for i in 0..offline_size {
             rank.push(i as i32)
         }
-        rank.shuffle(&mut thread_rng());
+        rank.shuffle(&mut rng);
         Ranking {
             offline_nodes_available: off_available,
             offline_nodes_rank: rank,
@@ -66,7 +91,7 @@ impl OnlineAlgorithm<usize, OfflineInfo> for Ranking {
         }
     }
 
-    fn dispatch(self: &mut Self, online_adjacent: &Vec<usize>) -> Option<usize> {
+    fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
         let available_offline_nodes = util::get_available_offline_nodes_in_onlineadj(
             &self.offline_nodes_available,
             online_adjacent,
@@ -90,11 +115,42 @@ impl OnlineAlgorithm<usize, OfflineInfo> for Ranking {
         }
     }
 
-    fn alg_output(self: Self) -> f64 {
+    fn alg_output(self) -> f64 {
         self.alg as f64
     }
 }
 
+/// `exact_ranking_ALG` runs Ranking once for every permutation of the
+/// offline nodes, so it refuses instances with more offline nodes than this.
+pub const MAX_EXACT_RANKING_OFFLINE_SIZE: usize = 10;
+
+impl<Key> OnlineAdversarialBigraph<Key> {
+    /// The exact expected ALG of `Ranking`, averaged over all the n! rank
+    /// permutations of the n offline nodes instead of sampled ones.
+    #[allow(non_snake_case)]
+    pub fn exact_ranking_ALG(&self) -> f64 {
+        let offline_size = self.offline_info();
+        assert!(
+            offline_size <= MAX_EXACT_RANKING_OFFLINE_SIZE,
+            "exact_ranking_ALG supports at most {} offline nodes, got {}",
+            MAX_EXACT_RANKING_OFFLINE_SIZE,
+            offline_size
+        );
+        let mut alg_sum = 0.;
+        let mut permutations = 0;
+        util::for_each_permutation(offline_size, |permutation| {
+            let mut ranking =
+                Ranking::with_ranks(permutation.iter().map(|&rank| rank as i32).collect());
+            if let Err(violation) = drive(offline_size, self.iter(), &mut ranking, |_, _| {}) {
+                panic!("{}", violation);
+            }
+            alg_sum += ranking.alg_output();
+            permutations += 1;
+        });
+        alg_sum / permutations as f64
+    }
+}
+
 pub mod example {
     use crate::{bigraph::Bigraph, papers::algorithm::OnlineAdversarialBigraph};
 
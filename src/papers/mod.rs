@@ -1,6 +1,14 @@
 pub mod adwords;
 pub mod algorithm;
+pub mod b_matching;
 pub mod reuseableresource;
 mod util;
+pub mod estimator;
+pub mod free_disposal;
+pub mod instance;
+pub mod known_iid;
 pub mod kvv90;
+pub mod outcome;
 pub mod stochastic_reward;
+pub mod validation;
+pub mod vertex_weighted;
@@ -0,0 +1,54 @@
+/// What the algorithm decided for one online node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision<Key> {
+    pub online: Key,
+    /// The offline node it is matched to, or probed in stochastic rewards.
+    pub offline: Option<Key>,
+    /// Weight, bid or probability of the chosen edge.
+    pub weight: Option<f64>,
+    /// Whether the probe succeeded, only for stochastic rewards.
+    pub success: Option<bool>,
+}
+
+/// The state of one offline node after the last arrival.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfflineState<Key> {
+    pub key: Key,
+    /// Number of matches, spent budget or probed probability,
+    /// depending on the model.
+    pub load: f64,
+    pub available: bool,
+}
+
+/// The structured result of a single run of an online algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome<Key> {
+    /// One decision per online node, in arrival order.
+    pub decisions: Vec<Decision<Key>>,
+    /// One state per offline node, in the order of `u_nodes`.
+    pub offline: Vec<OfflineState<Key>>,
+    pub objective: f64,
+}
+
+impl<Key: Copy> Outcome<Key> {
+    /// The (offline, online) pairs of the run, for stochastic rewards
+    /// only the successful probes.
+    pub fn matched_edges(&self) -> Vec<(Key, Key)> {
+        self.decisions
+            .iter()
+            .filter(|d| d.success != Some(false))
+            .filter_map(|d| d.offline.map(|u| (u, d.online)))
+            .collect()
+    }
+}
+
+/// The result of a single run of a fractional algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FractionalOutcome<Key> {
+    /// The fraction of every online node assigned to each offline node,
+    /// in arrival order.
+    pub allocations: Vec<Vec<(Key, f64)>>,
+    /// One state per offline node, its load is the total fraction it got.
+    pub offline: Vec<OfflineState<Key>>,
+    pub objective: f64,
+}
@@ -0,0 +1,116 @@
+use crate::papers::util::least_used;
+use crate::papers::vertex_weighted::agkm11::psi;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::heterogeneous::{algorithm::ReusableAlgorithm, OfflineInfo};
+
+/// Ranking-Based Allocation of Goyal, Iyer and Udwani (2020) for reusable
+/// offline nodes with capacities: every unit k of every offline node u
+/// gets a seed y_uk drawn uniformly from [0, 1) at `init`, and every online
+/// node takes the neighbour whose best free unit has the largest
+/// 1 - e^(y_uk - 1), using that unit. A unit keeps its seed when it is
+/// back, which is why the units are told apart by the arrival they were
+/// matched at. 1 - 1/e-competitive as the capacities grow, for arbitrary
+/// durations.
+#[derive(Debug)]
+pub struct RBA {
+    seeds: Vec<Vec<f64>>,
+    // the units of every offline node which aren't in use
+    free_units: Vec<Vec<usize>>,
+    // the unit taken by every arrival so far
+    taken: Vec<Option<usize>>,
+    alg: usize,
+}
+
+impl ReusableAlgorithm<OfflineInfo> for RBA {
+    fn init(offline_info: OfflineInfo) -> Self {
+        Self::init_with_rng(offline_info, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(offline_info: OfflineInfo, mut rng: StdRng) -> Self {
+        let capacities = offline_info.capacities;
+        RBA {
+            seeds: capacities
+                .iter()
+                .map(|&c| (0..c).map(|_| rng.gen_range(0. ..1.)).collect())
+                .collect(),
+            free_units: capacities.iter().map(|&c| (0..c).collect()).collect(),
+            taken: Vec::new(),
+            alg: 0,
+        }
+    }
+
+    fn release(&mut self, offline_node: usize, matched_at: usize) {
+        let unit = self.taken[matched_at]
+            .take()
+            .expect("released a unit which wasn't taken");
+        self.free_units[offline_node].push(unit);
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
+        let mut best: Option<(usize, usize, f64)> = None;
+        for &u in online_adjacent.iter() {
+            for (i, &k) in self.free_units[u].iter().enumerate() {
+                let score = psi(self.seeds[u][k]);
+                if best.map_or(true, |(_, _, best_score)| score > best_score) {
+                    best = Some((u, i, score));
+                }
+            }
+        }
+        match best {
+            Some((u, i, _)) => {
+                let unit = self.free_units[u].swap_remove(i);
+                self.taken.push(Some(unit));
+                self.alg += 1;
+                Some(u)
+            }
+            None => {
+                self.taken.push(None);
+                None
+            }
+        }
+    }
+
+    fn alg_output(self) -> f64 {
+        self.alg as f64
+    }
+}
+
+/// BALANCE for reusable offline nodes with capacities, the water-filling
+/// of `b_matching::kp00::Balance` where a unit counts again once it is
+/// back: every online node takes the neighbour with the smallest fraction
+/// of its units in use, among those with a free unit. Deterministic, and
+/// 1 - 1/e-competitive as the capacities grow when every offline node
+/// has a fixed duration (Goyal, Iyer and Udwani).
+#[derive(Debug)]
+pub struct Balance {
+    capacities: Vec<usize>,
+    in_use: Vec<usize>,
+    alg: usize,
+}
+
+impl ReusableAlgorithm<OfflineInfo> for Balance {
+    fn init(offline_info: OfflineInfo) -> Self {
+        let capacities = offline_info.capacities;
+        Balance {
+            in_use: vec![0; capacities.len()],
+            capacities,
+            alg: 0,
+        }
+    }
+
+    fn release(&mut self, offline_node: usize, _matched_at: usize) {
+        self.in_use[offline_node] -= 1;
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
+        let u = least_used(online_adjacent, &self.in_use, &self.capacities)?;
+        self.in_use[u] += 1;
+        self.alg += 1;
+        Some(u)
+    }
+
+    fn alg_output(self) -> f64 {
+        self.alg as f64
+    }
+}
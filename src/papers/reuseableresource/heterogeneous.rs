@@ -0,0 +1,566 @@
+use crate::bigraph::Bigraph;
+use crate::error::Error;
+use crate::offline::reusable::stochastic_reusable_matching_lp;
+use crate::papers::instance::{ArrivalIter, Instance, Simulate};
+use crate::papers::outcome::{Decision, OfflineState, Outcome};
+use crate::papers::validation::{check_adjacent, Violation};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use self::algorithm::ReusableAlgorithm;
+
+/// How many arrivals an offline node is in use once it is matched, it is
+/// back for the arrival `duration` after the one it was matched to.
+/// A duration of 0 counts as 1, like in `identical`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Duration {
+    Fixed(usize),
+    /// Drawn when the offline node is matched, independently of everything
+    /// else, from the (duration, probability) pairs adding up to 1.
+    Random(Vec<(usize, f64)>),
+}
+
+impl Duration {
+    /// A duration drawn uniformly from min, ..., max.
+    pub fn uniform(min: usize, max: usize) -> Self {
+        assert!(min <= max);
+        let p = 1. / (max - min + 1) as f64;
+        Duration::Random((min..=max).map(|d| (d, p)).collect())
+    }
+
+    pub fn sample(&self, rng: &mut StdRng) -> usize {
+        let d = match self {
+            Duration::Fixed(d) => *d,
+            Duration::Random(distribution) => {
+                let mut x = rng.gen_range(0. ..1.);
+                let mut sampled = distribution[distribution.len() - 1].0;
+                for &(d, p) in distribution.iter() {
+                    if x < p {
+                        sampled = d;
+                        break;
+                    }
+                    x -= p;
+                }
+                sampled
+            }
+        };
+        usize::max(d, 1)
+    }
+
+    pub fn mean(&self) -> f64 {
+        match self {
+            Duration::Fixed(d) => usize::max(*d, 1) as f64,
+            Duration::Random(distribution) => distribution
+                .iter()
+                .map(|&(d, p)| usize::max(d, 1) as f64 * p)
+                .sum(),
+        }
+    }
+
+    /// `survival(horizon)[s]` is the probability that the offline node is
+    /// still in use s arrivals after it was matched, for s < `horizon` only
+    /// since no instance lasts longer than its arrivals.
+    pub fn survival(&self, horizon: usize) -> Vec<f64> {
+        let distribution = match self {
+            Duration::Fixed(d) => vec![(*d, 1.)],
+            Duration::Random(distribution) => distribution.clone(),
+        };
+        let longest = distribution
+            .iter()
+            .map(|&(d, _)| usize::max(d, 1))
+            .max()
+            .unwrap_or(1);
+        (0..usize::min(longest, horizon))
+            .map(|s| {
+                distribution
+                    .iter()
+                    .filter(|&&(d, _)| usize::max(d, 1) > s)
+                    .map(|&(_, p)| p)
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn is_valid(&self) -> bool {
+        match self {
+            Duration::Fixed(_) => true,
+            Duration::Random(distribution) => {
+                !distribution.is_empty()
+                    && distribution.iter().all(|&(_, p)| p >= 0.)
+                    && (distribution.iter().map(|&(_, p)| p).sum::<f64>() - 1.).abs() < 1e-9
+            }
+        }
+    }
+}
+
+/// What the algorithms of the model are initialized with.
+#[derive(Debug, Clone)]
+pub struct OfflineInfo {
+    pub durations: Vec<Duration>,
+    /// The number of units of every offline node, each of them is in use
+    /// for its own duration.
+    pub capacities: Vec<usize>,
+}
+
+impl<Key: Copy> Bigraph<Key> {
+    /// Gives every offline node its own usage duration, `durations[i]` for
+    /// `u_nodes[i]`, fixed or drawn every time it is matched.
+    /// Panics on invalid durations, `try_into_heterogeneous_reuseable`
+    /// returns an `Error` instead.
+    pub fn into_heterogeneous_reuseable(
+        self,
+        durations: Vec<Duration>,
+    ) -> HeterogeneousReusable<Key>
+    where
+        Key: std::fmt::Debug,
+    {
+        self.try_into_heterogeneous_reuseable(durations)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_into_heterogeneous_reuseable(
+        self,
+        durations: Vec<Duration>,
+    ) -> Result<HeterogeneousReusable<Key>, Error<Key>> {
+        let capacities = vec![1; self.u_nodes.len()];
+        self.try_into_reuseable_with_capacities(durations, capacities)
+    }
+
+    /// Same as `into_heterogeneous_reuseable`, but offline node `u_nodes[i]`
+    /// has `capacities[i]` units, which can be used at the same time.
+    pub fn into_reuseable_with_capacities(
+        self,
+        durations: Vec<Duration>,
+        capacities: Vec<usize>,
+    ) -> HeterogeneousReusable<Key>
+    where
+        Key: std::fmt::Debug,
+    {
+        self.try_into_reuseable_with_capacities(durations, capacities)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_into_reuseable_with_capacities(
+        self,
+        durations: Vec<Duration>,
+        capacities: Vec<usize>,
+    ) -> Result<HeterogeneousReusable<Key>, Error<Key>> {
+        if durations.len() != self.u_nodes.len() {
+            return Err(Error::DurationLengthMismatch {
+                offline_size: self.u_nodes.len(),
+                durations: durations.len(),
+            });
+        }
+        for (u, duration) in durations.iter().enumerate() {
+            if !duration.is_valid() {
+                return Err(Error::InvalidDuration {
+                    offline: self.u_nodes[u],
+                });
+            }
+        }
+        if capacities.len() != self.u_nodes.len() {
+            return Err(Error::CapacityLengthMismatch {
+                offline_size: self.u_nodes.len(),
+                capacities: capacities.len(),
+            });
+        }
+        Ok(HeterogeneousReusable {
+            bigraph: self,
+            durations,
+            capacities,
+            opt: None,
+        })
+    }
+}
+
+/// Matching with reusable offline nodes (Gong et al., Goyal, Iyer and
+/// Udwani) where every offline node has its own usage duration, which may
+/// be random, and may have several units. The algorithm doesn't see the
+/// durations drawn, only when the units are back.
+#[derive(Debug)]
+pub struct HeterogeneousReusable<Key> {
+    bigraph: Bigraph<Key>,
+    durations: Vec<Duration>,
+    capacities: Vec<usize>,
+    opt: Option<f64>,
+}
+
+pub mod algorithm {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    pub trait ReusableAlgorithm<OfflineInfo>
+    where
+        Self: Sized,
+    {
+        fn init(offline_info: OfflineInfo) -> Self;
+
+        /// Randomized algorithms must draw all their randomness from `rng`,
+        /// the durations are drawn by the model.
+        fn init_with_rng(offline_info: OfflineInfo, rng: StdRng) -> Self {
+            let _ = rng;
+            Self::init(offline_info)
+        }
+
+        /// The unit of the offline node matched at the arrival `matched_at`
+        /// (counting from 0) is back, called before the dispatch of the
+        /// first arrival it is available for.
+        fn release(&mut self, offline_node: usize, matched_at: usize);
+
+        #[allow(clippy::ptr_arg)]
+        fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize>;
+
+        fn alg_output(self) -> f64;
+    }
+
+    /// RANKING with the offline nodes released by the model instead of
+    /// counting down a shared duration: every offline node gets a uniformly
+    /// random rank at `init` and every online node takes its best ranked
+    /// neighbour with a unit which isn't in use.
+    #[derive(Debug)]
+    pub struct Ranking {
+        // the number of units of every offline node which aren't in use
+        offline_nodes_available: Vec<usize>,
+        offline_nodes_rank: Vec<i32>,
+        alg: usize,
+    }
+
+    impl ReusableAlgorithm<super::OfflineInfo> for Ranking {
+        fn init(offline_info: super::OfflineInfo) -> Self {
+            Self::init_with_rng(offline_info, StdRng::from_entropy())
+        }
+
+        fn init_with_rng(offline_info: super::OfflineInfo, mut rng: StdRng) -> Self {
+            use rand::seq::SliceRandom;
+            let offline_size = offline_info.durations.len();
+            let mut offline_nodes_rank: Vec<i32> = (0..offline_size as i32).collect();
+            offline_nodes_rank.shuffle(&mut rng);
+            Ranking {
+                offline_nodes_available: offline_info.capacities,
+                offline_nodes_rank,
+                alg: 0,
+            }
+        }
+
+        fn release(&mut self, offline_node: usize, _matched_at: usize) {
+            self.offline_nodes_available[offline_node] += 1;
+        }
+
+        fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
+            let u = *online_adjacent
+                .iter()
+                .filter(|&&u| self.offline_nodes_available[u] > 0)
+                .min_by_key(|&&u| self.offline_nodes_rank[u])?;
+            self.offline_nodes_available[u] -= 1;
+            self.alg += 1;
+            Some(u)
+        }
+
+        fn alg_output(self) -> f64 {
+            self.alg as f64
+        }
+    }
+}
+
+impl<Key> Instance for HeterogeneousReusable<Key> {
+    type Key = Key;
+    type AdjType = usize;
+    type OfflineInfo = OfflineInfo;
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> OfflineInfo {
+        OfflineInfo {
+            durations: self.durations.clone(),
+            capacities: self.capacities.clone(),
+        }
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, usize> {
+        ArrivalIter::new(&self.bigraph.v_adjacency_list)
+    }
+
+    fn OPT(&self) -> f64 {
+        HeterogeneousReusable::OPT(self)
+    }
+}
+
+impl<Key, Alg> Simulate<Alg> for HeterogeneousReusable<Key>
+where
+    Key: Copy,
+    Alg: ReusableAlgorithm<OfflineInfo>,
+{
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation> {
+        self.run::<Alg>(rng)
+    }
+}
+
+impl<Key> HeterogeneousReusable<Key> {
+    pub fn durations(&self) -> &Vec<Duration> {
+        &self.durations
+    }
+
+    pub fn capacities(&self) -> &Vec<usize> {
+        &self.capacities
+    }
+
+    /// The one given by `set_opt`, otherwise `fractional_OPT`.
+    #[allow(non_snake_case)]
+    pub fn OPT(&self) -> f64 {
+        self.opt.unwrap_or_else(|| self.fractional_OPT())
+    }
+
+    /// Overrides `OPT`, e.g. with the known optimum of a hand-built example.
+    pub fn set_opt(&mut self, opt: f64) {
+        self.opt = Some(opt);
+    }
+
+    /// The LP benchmark, see
+    /// `offline::reusable::stochastic_reusable_matching_lp`. With the same
+    /// fixed duration for every offline node it is the `fractional_OPT`
+    /// of `identical`.
+    #[allow(non_snake_case)]
+    pub fn fractional_OPT(&self) -> f64 {
+        stochastic_reusable_matching_lp(
+            &self.bigraph.v_adjacency_list,
+            &self
+                .durations
+                .iter()
+                .map(|d| d.survival(self.bigraph.v_nodes.len()))
+                .collect::<Vec<_>>(),
+            &self.capacities,
+        )
+    }
+
+    /// The average ALG over `precision` trials, each drawing the durations
+    /// anew. Panics if the algorithm makes an invalid decision, `run`
+    /// reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn ALG<Alg: ReusableAlgorithm<OfflineInfo>>(&self, precision: usize) -> f64 {
+        self.ALG_with_rng::<Alg>(precision, &mut StdRng::from_entropy())
+    }
+
+    /// Same as `ALG`, but every trial is seeded from `rng`.
+    #[allow(non_snake_case)]
+    pub fn ALG_with_rng<Alg: ReusableAlgorithm<OfflineInfo>>(
+        &self,
+        precision: usize,
+        rng: &mut StdRng,
+    ) -> f64 {
+        let mut alg_sum: f64 = 0.;
+        for _ in 0..precision {
+            let mut alg = Alg::init_with_rng(self.offline_info(), StdRng::seed_from_u64(rng.gen()));
+            let result = self.drive(
+                &mut alg,
+                &mut StdRng::seed_from_u64(rng.gen()),
+                |_, _, _| {},
+            );
+            if let Err(violation) = result {
+                panic!("{}", violation);
+            }
+            alg_sum += alg.alg_output();
+        }
+        alg_sum / precision as f64
+    }
+
+    fn drive<Alg, F>(
+        &self,
+        alg: &mut Alg,
+        durations: &mut StdRng,
+        observe: F,
+    ) -> Result<(), Violation>
+    where
+        Alg: ReusableAlgorithm<OfflineInfo>,
+        F: FnMut(usize, Option<usize>, usize),
+    {
+        drive(
+            &self.durations,
+            &self.capacities,
+            &self.bigraph.v_adjacency_list,
+            alg,
+            durations,
+            observe,
+        )
+    }
+}
+
+impl<Key: Copy> HeterogeneousReusable<Key> {
+    /// Runs a single trial of the algorithm and reports every decision,
+    /// with the duration drawn as its weight, how many times every offline
+    /// node was used and whether one of its units is back after the last
+    /// arrival, or the first decision which takes an offline node with all
+    /// of its units in use.
+    pub fn run<Alg: ReusableAlgorithm<OfflineInfo>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation> {
+        let v_nodes = &self.bigraph.v_nodes;
+        let u_nodes = &self.bigraph.u_nodes;
+        let mut alg = Alg::init_with_rng(self.offline_info(), StdRng::seed_from_u64(rng.gen()));
+        let mut decisions = Vec::with_capacity(v_nodes.len());
+        let mut loads = vec![0.; u_nodes.len()];
+        let mut available_at = vec![vec![]; u_nodes.len()];
+        self.drive(
+            &mut alg,
+            &mut StdRng::seed_from_u64(rng.gen()),
+            |t, alg_choose, duration| {
+                if let Some(u) = alg_choose {
+                    loads[u] += 1.;
+                    available_at[u].push(t.saturating_add(duration));
+                }
+                decisions.push(Decision {
+                    online: v_nodes[t],
+                    offline: alg_choose.map(|u| u_nodes[u]),
+                    weight: alg_choose.map(|_| duration as f64),
+                    success: None,
+                });
+            },
+        )?;
+        let offline = u_nodes
+            .iter()
+            .enumerate()
+            .map(|(u, &key)| OfflineState {
+                key,
+                load: loads[u],
+                available: available_at[u]
+                    .iter()
+                    .filter(|&&back| back > v_nodes.len())
+                    .count()
+                    < self.capacities[u],
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective: alg.alg_output(),
+        })
+    }
+}
+
+// Feeds the online nodes to `alg` in arrival order, first releasing the
+// units which are back. Checks that every choice is an adjacent offline
+// node with a unit which isn't in use, draws its duration from `rng` and
+// reports the choice and the duration (0 without a choice) to `observe`
+// along with the arrival time.
+fn drive<Alg, I, F>(
+    durations: &[Duration],
+    capacities: &[usize],
+    arrivals: I,
+    alg: &mut Alg,
+    rng: &mut StdRng,
+    mut observe: F,
+) -> Result<(), Violation>
+where
+    Alg: ReusableAlgorithm<OfflineInfo>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<usize>>,
+    F: FnMut(usize, Option<usize>, usize),
+{
+    let offline_size = durations.len();
+    let mut in_use = vec![0; offline_size];
+    // (the arrival a unit is back for, its offline node, the arrival it was
+    // matched at)
+    let mut returns = BinaryHeap::new();
+    for (t, online_adj) in arrivals.into_iter().enumerate() {
+        while let Some(&Reverse((back, u, matched_at))) = returns.peek() {
+            if back > t {
+                break;
+            }
+            returns.pop();
+            in_use[u] -= 1;
+            alg.release(u, matched_at);
+        }
+        let online_adj = online_adj.borrow();
+        let alg_choose = alg.dispatch(online_adj);
+        let mut duration = 0;
+        if let Some(u) = alg_choose {
+            check_adjacent(t, u, offline_size, online_adj, |&u| u)?;
+            if in_use[u] >= capacities[u] {
+                // the first unit back, never without units
+                let available_at = returns
+                    .iter()
+                    .filter(|&&Reverse((_, w, _))| w == u)
+                    .map(|&Reverse((back, _, _))| back)
+                    .min()
+                    .unwrap_or(usize::MAX);
+                return Err(Violation::Busy {
+                    online: t,
+                    offline: u,
+                    available_at,
+                });
+            }
+            duration = durations[u].sample(rng);
+            in_use[u] += 1;
+            returns.push(Reverse((t.saturating_add(duration), u, t)));
+        }
+        observe(t, alg_choose, duration);
+    }
+    Ok(())
+}
+
+pub mod example {
+    use super::{Duration, HeterogeneousReusable};
+    use crate::bigraph::Bigraph;
+    use crate::papers::util::random_edges;
+    use rand::{rngs::StdRng, Rng};
+
+    /// The graph of `identical::example::z_graph_with_duration` where
+    /// offline node u is in use for `durations[u]`.
+    pub fn z_graph(durations: Vec<Duration>) -> HeterogeneousReusable<usize> {
+        let n = durations.len();
+        let mut edges = Vec::new();
+        for v in 0..n {
+            for u in v..n {
+                edges.push((u, v));
+            }
+        }
+        Bigraph::from_edges(&edges).into_heterogeneous_reuseable(durations)
+    }
+
+    /// `b_matching::graph::example::upper_triangular` with reusable offline
+    /// nodes: n offline nodes with c units, each in use for d arrivals, and
+    /// c online nodes of every type i adjacent to the offline nodes
+    /// i, ..., n - 1, arriving type by type and listing their neighbours
+    /// from the last one. With c = 1 it is the graph of `z_graph` with
+    /// every duration d. Once d is at least n * c no unit is ever back, and
+    /// BALANCE and RBA tend to 1 - 1/e as n and c grow while OPT stays n * c.
+    pub fn z_graph_with_capacity(n: usize, d: usize, c: usize) -> HeterogeneousReusable<usize> {
+        let mut edges = Vec::new();
+        for i in 0..n {
+            for copy in 0..c {
+                for u in (i..n).rev() {
+                    edges.push((u, i * c + copy));
+                }
+            }
+        }
+        Bigraph::from_edges(&edges)
+            .into_reuseable_with_capacities(vec![Duration::Fixed(d); n], vec![c; n])
+    }
+
+    /// `offline_size` offline and `online_size` online nodes, every pair is
+    /// an edge with probability `density`, and every offline node is in use
+    /// for a duration drawn uniformly from 1, ..., d_u with d_u drawn
+    /// uniformly from 1, ..., `max_duration`.
+    pub fn random(
+        offline_size: usize,
+        online_size: usize,
+        density: f64,
+        max_duration: usize,
+        rng: &mut StdRng,
+    ) -> HeterogeneousReusable<usize> {
+        let graph = Bigraph::from_edges(&random_edges(offline_size, online_size, density, rng));
+        let durations = graph
+            .u_nodes
+            .iter()
+            .map(|_| Duration::uniform(1, rng.gen_range(1..=max_duration)))
+            .collect();
+        graph.into_heterogeneous_reuseable(durations)
+    }
+}
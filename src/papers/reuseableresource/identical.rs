@@ -1,8 +1,15 @@
 use crate::bigraph::Bigraph;
+use crate::offline::reusable::{reusable_matching, reusable_matching_lp};
 use crate::papers::algorithm::algorithm::OnlineAlgorithm;
+use crate::papers::instance::{ArrivalIter, Instance, Simulate};
+use crate::papers::outcome::{Decision, OfflineState, Outcome};
+use crate::papers::reuseableresource::heterogeneous::{Duration, HeterogeneousReusable};
+use crate::papers::validation::{check_adjacent, Violation};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::borrow::Borrow;
 
 impl<Key> Bigraph<Key> {
-    pub fn into_reuseable_online(self: Self, duration: usize) -> OnlineAdversarialBigraph<Key> {
+    pub fn into_reuseable_online(self, duration: usize) -> OnlineAdversarialBigraph<Key> {
         OnlineAdversarialBigraph {
             bigraph: self,
             duration,
@@ -17,58 +24,257 @@ pub struct OnlineAdversarialBigraph<Key> {
     opt: Option<f64>,
 }
 
-pub struct OnlineAdversarialBigraphIter<'a> {
-    online_adjacency_list: &'a Vec<Vec<usize>>,
-    online_index: usize,
+impl<Key: Copy> OnlineAdversarialBigraph<Key> {
+    /// The same instance in the heterogeneous model, where every offline
+    /// node has one unit in use for the fixed duration, so that the
+    /// algorithms of `giu20` run on it. An OPT given by `set_opt` carries
+    /// over.
+    pub fn into_heterogeneous(self) -> HeterogeneousReusable<Key> {
+        let durations = vec![Duration::Fixed(self.duration); self.bigraph.u_nodes.len()];
+        let mut heterogeneous = self
+            .bigraph
+            .try_into_heterogeneous_reuseable(durations)
+            .unwrap_or_else(|_| unreachable!("fixed durations are valid"));
+        if let Some(opt) = self.opt {
+            heterogeneous.set_opt(opt);
+        }
+        heterogeneous
+    }
 }
 
-impl<'a, Key> OnlineAdversarialBigraph<Key> {
-    pub fn iter(self: &'a Self) -> OnlineAdversarialBigraphIter<'a> {
-        OnlineAdversarialBigraphIter {
-            online_adjacency_list: &self.bigraph.v_adjacency_list,
-            online_index: 0,
-        }
+pub type OnlineAdversarialBigraphIter<'a> = ArrivalIter<'a, usize>;
+
+impl<Key> Instance for OnlineAdversarialBigraph<Key> {
+    type Key = Key;
+    type AdjType = usize;
+    type OfflineInfo = (usize, usize);
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> (usize, usize) {
+        (self.bigraph.u_nodes.len(), self.duration)
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, usize> {
+        self.iter()
+    }
+
+    fn OPT(&self) -> f64 {
+        OnlineAdversarialBigraph::OPT(self)
+    }
+}
+
+impl<Key, Alg> Simulate<Alg> for OnlineAdversarialBigraph<Key>
+where
+    Key: Copy,
+    Alg: OnlineAlgorithm<usize, (usize, usize)>,
+{
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation> {
+        self.run::<Alg>(rng)
     }
 }
 
 impl<'a, Key> OnlineAdversarialBigraph<Key> {
+    pub fn iter(&'a self) -> OnlineAdversarialBigraphIter<'a> {
+        ArrivalIter::new(&self.bigraph.v_adjacency_list)
+    }
+}
+
+impl<Key> OnlineAdversarialBigraph<Key> {
+    /// The one given by `set_opt`, otherwise `integral_OPT`. That is
+    /// exponential in the worst case, on large instances set it or
+    /// compare against `fractional_OPT`.
     #[allow(non_snake_case)]
-    pub fn OPT(self: &Self) -> f64 {
-        // temporary unsound
-        if let Some(opt) = self.opt {
-            return opt;
-        }
-        self.bigraph.v_nodes.len() as f64
+    pub fn OPT(&self) -> f64 {
+        self.opt.unwrap_or_else(|| self.integral_OPT())
+    }
+
+    /// The clairvoyant offline optimum which respects the duration,
+    /// computed by branch and bound, which is exponential in the worst case.
+    #[allow(non_snake_case)]
+    pub fn integral_OPT(&self) -> f64 {
+        self.opt_matching().0 as f64
+    }
+
+    /// Overrides `OPT`, e.g. with the known optimum of a hand-built example.
+    pub fn set_opt(&mut self, opt: f64) {
+        self.opt = Some(opt);
+    }
+
+    /// The matching behind `integral_OPT`, returns its size and the
+    /// offline node matched to every online node (in arrival order).
+    pub fn opt_matching(&self) -> (usize, Vec<Option<usize>>) {
+        reusable_matching(
+            self.bigraph.u_nodes.len(),
+            &self.bigraph.v_adjacency_list,
+            self.duration,
+        )
+    }
+
+    /// The LP relaxation of the offline optimum, at least `integral_OPT`.
+    /// It is solved by the dense simplex of `offline::lp`, see there for
+    /// the size it can handle.
+    #[allow(non_snake_case)]
+    pub fn fractional_OPT(&self) -> f64 {
+        reusable_matching_lp(
+            self.bigraph.u_nodes.len(),
+            &self.bigraph.v_adjacency_list,
+            self.duration,
+        )
+    }
+
+    /// Panics if the algorithm makes an invalid decision,
+    /// `run` reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn ALG<Alg: OnlineAlgorithm<usize, (usize, usize)>>(&self) -> f64 {
+        self.ALG_with_rng::<Alg>(&mut StdRng::from_entropy())
     }
 
+    /// Same as `ALG`, but the algorithm draws its randomness from `rng`.
     #[allow(non_snake_case)]
-    pub fn ALG<Alg: OnlineAlgorithm<usize, (usize, usize)>>(self: &Self) -> f64 {
-        let mut alg = Alg::init((self.bigraph.u_nodes.len(), self.duration));
-        for online_adj in self.iter() {
-            // println!("{:?}", online_adj);
-            let alg_choose = alg.dispatch(online_adj);
-            drop(alg_choose);
+    pub fn ALG_with_rng<Alg: OnlineAlgorithm<usize, (usize, usize)>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> f64 {
+        let mut alg = Alg::init_with_rng(
+            (self.bigraph.u_nodes.len(), self.duration),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+        if let Err(violation) = self.drive(&mut alg, |_, _| {}) {
+            panic!("{}", violation);
         }
         alg.alg_output()
     }
+
+    fn drive<Alg, F>(&self, alg: &mut Alg, observe: F) -> Result<(), Violation>
+    where
+        Alg: OnlineAlgorithm<usize, (usize, usize)>,
+        F: FnMut(usize, Option<usize>),
+    {
+        drive(
+            self.bigraph.u_nodes.len(),
+            self.duration,
+            self.iter(),
+            alg,
+            observe,
+        )
+    }
 }
 
-impl<'a> Iterator for OnlineAdversarialBigraphIter<'a> {
-    type Item = &'a Vec<usize>;
+/// Runs the algorithm on online nodes which aren't known in advance,
+/// every item of `arrivals` is the adjacency list of the next online node.
+/// Returns the first decision which takes an offline node in use
+/// as a `Violation`.
+#[allow(non_snake_case)]
+pub fn stream_ALG<Alg, I>(
+    offline_size: usize,
+    duration: usize,
+    arrivals: I,
+    rng: &mut StdRng,
+) -> Result<f64, Violation>
+where
+    Alg: OnlineAlgorithm<usize, (usize, usize)>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<usize>>,
+{
+    let mut alg = Alg::init_with_rng((offline_size, duration), StdRng::seed_from_u64(rng.gen()));
+    drive(offline_size, duration, arrivals, &mut alg, |_, _| {})?;
+    Ok(alg.alg_output())
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.online_index == self.online_adjacency_list.len() {
-            None
-        } else {
-            self.online_index += 1;
-            Some(&self.online_adjacency_list[self.online_index - 1])
+// Feeds the online nodes to `alg` in arrival order, checks that every
+// choice is an adjacent offline node which is back from its last use
+// and reports it to `observe` along with the arrival time.
+fn drive<Alg, I, F>(
+    offline_size: usize,
+    duration: usize,
+    arrivals: I,
+    alg: &mut Alg,
+    mut observe: F,
+) -> Result<(), Violation>
+where
+    Alg: OnlineAlgorithm<usize, (usize, usize)>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<usize>>,
+    F: FnMut(usize, Option<usize>),
+{
+    let mut available_at = vec![0; offline_size];
+    for (t, online_adj) in arrivals.into_iter().enumerate() {
+        let online_adj = online_adj.borrow();
+        let alg_choose = alg.dispatch(online_adj);
+        if let Some(u) = alg_choose {
+            check_adjacent(t, u, offline_size, online_adj, |&u| u)?;
+            if available_at[u] > t {
+                return Err(Violation::Busy {
+                    online: t,
+                    offline: u,
+                    available_at: available_at[u],
+                });
+            }
+            available_at[u] = t + usize::max(duration, 1);
         }
+        observe(t, alg_choose);
+    }
+    Ok(())
+}
+
+impl<Key: Copy> OnlineAdversarialBigraph<Key> {
+    /// Runs the algorithm once and reports every decision, how many times
+    /// every offline node was used and whether it is back after the last
+    /// arrival, keyed by the keys of the graph,
+    /// or the first decision which takes an offline node in use.
+    pub fn run<Alg: OnlineAlgorithm<usize, (usize, usize)>>(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation> {
+        let v_nodes = &self.bigraph.v_nodes;
+        let u_nodes = &self.bigraph.u_nodes;
+        let mut alg = Alg::init_with_rng(
+            (u_nodes.len(), self.duration),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+        let mut decisions = Vec::with_capacity(v_nodes.len());
+        let mut loads = vec![0.; u_nodes.len()];
+        // the arrival time from which every offline node is available again
+        let mut available_at = vec![0; u_nodes.len()];
+        self.drive(&mut alg, |t, alg_choose| {
+            if let Some(u) = alg_choose {
+                loads[u] += 1.;
+                available_at[u] = t + usize::max(self.duration, 1);
+            }
+            decisions.push(Decision {
+                online: v_nodes[t],
+                offline: alg_choose.map(|u| u_nodes[u]),
+                weight: alg_choose.map(|_| 1.),
+                success: None,
+            });
+        })?;
+        let offline = u_nodes
+            .iter()
+            .enumerate()
+            .map(|(u, &key)| OfflineState {
+                key,
+                load: loads[u],
+                available: available_at[u] <= v_nodes.len(),
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective: alg.alg_output(),
+        })
     }
 }
 
 pub mod algorithm {
     use crate::papers::algorithm::algorithm::OnlineAlgorithm;
-    use rand::thread_rng;
+    use rand::{rngs::StdRng, SeedableRng};
 
     pub struct Ranking {
         offline_nodes_available: Vec<i32>,
@@ -79,9 +285,12 @@ pub mod algorithm {
 
     impl OnlineAlgorithm<usize, (usize, usize)> for Ranking {
         fn init(input: (usize, usize)) -> Self {
+            Self::init_with_rng(input, StdRng::from_entropy())
+        }
+
+        fn init_with_rng(input: (usize, usize), mut rng: StdRng) -> Self {
             let (offline_size, duration) = input;
-            let mut vec = Vec::with_capacity(offline_size);
-            vec.resize(offline_size, 0);
+            let vec = vec![0; offline_size];
             use rand::seq::SliceRandom;
             let mut off_available = Vec::with_capacity(offline_size);
             off_available.resize(offline_size, true);
@@ -89,7 +298,7 @@ pub mod algorithm {
             for i in 0..offline_size {
                 offline_nodes_rank.push(i as i32)
             }
-            offline_nodes_rank.shuffle(&mut thread_rng());
+            offline_nodes_rank.shuffle(&mut rng);
             Ranking {
                 offline_nodes_available: vec,
                 offline_nodes_rank,
@@ -98,7 +307,7 @@ pub mod algorithm {
             }
         }
 
-        fn dispatch(self: &mut Self, online_adjacent: &Vec<usize>) -> Option<usize> {
+        fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
             let mut available_nodes = Vec::with_capacity(online_adjacent.len());
             for &offline_node in online_adjacent.iter() {
                 if self.offline_nodes_available[offline_node] == 0 {
@@ -131,7 +340,7 @@ pub mod algorithm {
             ans
         }
 
-        fn alg_output(self: Self) -> f64 {
+        fn alg_output(self) -> f64 {
             self.alg as f64
         }
     }
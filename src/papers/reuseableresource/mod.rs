@@ -1 +1,3 @@
+pub mod giu20;
+pub mod heterogeneous;
 pub mod identical;
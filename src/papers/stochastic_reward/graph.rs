@@ -1,23 +1,45 @@
+use crate::error::Error;
+use crate::offline::allocation::budgeted_allocation_lp;
+use crate::papers::instance::{random_order, ArrivalIter, Instance, Simulate};
+use crate::papers::outcome::{Decision, OfflineState, Outcome};
+use crate::papers::validation::{check_adjacent, Violation};
 use crate::weightedbigraph::WBigraph;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::borrow::Borrow;
 
-use self::algorithm::AdaptiveAlgorithm;
+use self::algorithm::{AdaptiveAlgorithm, NoneAdaptiveAlgorithm};
 
 pub type OfflineInfo = usize;
 pub type Prob = f64;
 
-impl<Key> WBigraph<Key, Prob> {
-    pub fn into_stochastic_reward(self: Self) -> StochasticReward<Key> {
-        for edge in self.nodes_edges.iter() {
-            let prob = edge.1;
-            assert!(
-                prob >= 0. && prob <= 1.,
-                "prob = {}, Probility should be in [0, 1]",
-                prob
-            )
+/// `adaptive_OPT` keeps a table over all the subsets of offline nodes,
+/// so it refuses instances with more offline nodes than this.
+pub const MAX_ADAPTIVE_OPT_OFFLINE_SIZE: usize = 20;
+
+impl<Key: Copy> WBigraph<Key, Prob> {
+    /// Panics if a probability isn't in [0, 1],
+    /// `try_into_stochastic_reward` returns an `Error` instead.
+    pub fn into_stochastic_reward(self) -> StochasticReward<Key>
+    where
+        Key: std::fmt::Debug,
+    {
+        self.try_into_stochastic_reward()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_into_stochastic_reward(self) -> Result<StochasticReward<Key>, Error<Key>> {
+        for &((u, v), prob) in self.nodes_edges.iter() {
+            if !(0. ..=1.).contains(&prob) {
+                return Err(Error::ProbabilityOutOfRange {
+                    offline: u,
+                    online: v,
+                    prob,
+                });
+            }
         }
-        StochasticReward {
+        Ok(StochasticReward {
             weighted_bigraph: self,
-        }
+        })
     }
 }
 
@@ -26,90 +48,541 @@ pub struct StochasticReward<Key> {
     pub weighted_bigraph: WBigraph<Key, Prob>,
 }
 
+pub type StochasticRewardIter<'a> = ArrivalIter<'a, (usize, Prob)>;
+
+impl<Key> Instance for StochasticReward<Key> {
+    type Key = Key;
+    type AdjType = (usize, Prob);
+    type OfflineInfo = OfflineInfo;
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.weighted_bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.weighted_bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> OfflineInfo {
+        self.weighted_bigraph.u_nodes.len()
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, (usize, Prob)> {
+        self.iter()
+    }
+
+    /// The expected LP benchmark, see `StochasticReward::OPT`.
+    fn OPT(&self) -> f64 {
+        StochasticReward::OPT(self)
+    }
+}
+
+impl<Key, Alg> Simulate<Alg> for StochasticReward<Key>
+where
+    Key: Copy,
+    Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+{
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation> {
+        self.adaptive_run::<Alg>(rng)
+    }
+}
+
 pub mod algorithm {
+    use rand::rngs::StdRng;
+
     pub trait AdaptiveAlgorithm<AdjType, OfflineInfo>
     {
         fn init(lenth: OfflineInfo) -> Self;
 
-        fn dispatch(
-            self: &mut Self,
-            online_adjacent: &Vec<AdjType>,
-        ) -> Option<(usize, super::Prob)>;
+        /// The randomness of the algorithm and of the outcomes it samples
+        /// in `query_success` must come from `rng`,
+        /// so that a run can be replayed from its seed.
+        fn init_with_rng(lenth: OfflineInfo, rng: StdRng) -> Self
+        where
+            Self: Sized,
+        {
+            let _ = rng;
+            Self::init(lenth)
+        }
 
-        fn query_success(
-            self: &mut Self,
-            offline_node: Option<(usize, super::Prob)>,
-        ) -> Option<bool>;
+        /// Called by the drivers before the first probe of every online
+        /// node. With patience (see `weighted`) `dispatch` is called again
+        /// for the same online node after a failed probe, so the algorithms
+        /// which keep state per online node start it here, not in `dispatch`.
+        fn next_online(&mut self) {}
 
-        fn alg_output(self: Self) -> f64;
+        #[allow(clippy::ptr_arg)]
+        fn dispatch(&mut self, online_adjacent: &Vec<AdjType>) -> Option<(usize, super::Prob)>;
+
+        fn query_success(&mut self, offline_node: Option<(usize, super::Prob)>) -> Option<bool>;
+
+        fn alg_output(self) -> f64;
     }
 
+    /// Commits every online node without seeing the outcomes of the earlier
+    /// probes, the driver samples them, see `non_adaptive_ALG`.
     pub trait NoneAdaptiveAlgorithm<AdjType, OfflineInfo>
     {
         fn init(lenth: OfflineInfo) -> Self;
 
-        fn dispatch(
-            self: &mut Self,
-            online_adjacent: &Vec<AdjType>,
-        ) -> Option<(usize, super::Prob)>;
+        fn init_with_rng(lenth: OfflineInfo, rng: StdRng) -> Self
+        where
+            Self: Sized,
+        {
+            let _ = rng;
+            Self::init(lenth)
+        }
+
+        #[allow(clippy::ptr_arg)]
+        fn dispatch(&mut self, online_adjacent: &Vec<AdjType>) -> Option<(usize, super::Prob)>;
     }
 }
 
 impl<'a, Key> StochasticReward<Key> {
-    pub fn iter(self: &'a Self) -> StochasticRewardIter<'a> {
-        StochasticRewardIter {
-            online_adjacency_list: &self.weighted_bigraph.v_adjacency_list,
-            online_index: 0,
-        }
+    pub fn iter(&'a self) -> StochasticRewardIter<'a> {
+        ArrivalIter::new(&self.weighted_bigraph.v_adjacency_list)
+    }
+
+    /// The expected LP benchmark of MP12:
+    ///     maximize sum(p_uv * x_uv)
+    ///     s.t. sum_u x_uv <= 1 for every online v,
+    ///          sum_v p_uv * x_uv <= 1 for every offline u,
+    /// i.e. every offline node receives at most total probability 1.
+    #[allow(non_snake_case)]
+    pub fn OPT(&self) -> f64 {
+        let budgets = vec![1.; self.weighted_bigraph.u_nodes.len()];
+        budgeted_allocation_lp(&budgets, &self.weighted_bigraph.v_adjacency_list)
     }
 
+    /// The exact offline adaptive optimum: the best policy which knows the
+    /// whole graph and the arrival order but only sees the outcome of a
+    /// probe after making it. It is a dynamic programming over the subsets
+    /// of still available offline nodes, so it is only for tiny instances.
     #[allow(non_snake_case)]
-    pub fn OPT(self: &Self) -> f64 {
-        // temporary unsound
-        self.weighted_bigraph.u_nodes.len() as f64
+    pub fn adaptive_OPT(&self) -> f64 {
+        let offline_size = self.weighted_bigraph.u_nodes.len();
+        assert!(
+            offline_size <= MAX_ADAPTIVE_OPT_OFFLINE_SIZE,
+            "adaptive_OPT supports at most {} offline nodes, got {}",
+            MAX_ADAPTIVE_OPT_OFFLINE_SIZE,
+            offline_size
+        );
+        // value[mask] is the optimal expected reward of the remaining arrivals
+        // when exactly the offline nodes in mask are available
+        let mut value = vec![0.; 1 << offline_size];
+        for online_adj in self.weighted_bigraph.v_adjacency_list.iter().rev() {
+            let next = value.clone();
+            for mask in 0..value.len() {
+                for &(u, p) in online_adj.iter() {
+                    if mask & (1 << u) != 0 {
+                        let probe = p * (1. + next[mask ^ (1 << u)]) + (1. - p) * next[mask];
+                        if probe > value[mask] {
+                            value[mask] = probe;
+                        }
+                    }
+                }
+            }
+        }
+        value[value.len() - 1]
     }
 
-    fn _adaptive_alg<Alg>(self: &Self) -> f64
+    fn _adaptive_alg<Alg>(&self, rng: &mut StdRng) -> f64
     where
         Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo> + std::fmt::Debug,
     {
-        let mut alg = Alg::init(self.weighted_bigraph.u_nodes.len());
-        for online_adj in self.iter() {
-            let alg_choose = alg.dispatch(online_adj);
-            alg.query_success(alg_choose);
+        let mut alg = Alg::init_with_rng(
+            self.weighted_bigraph.u_nodes.len(),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+        if let Err(violation) = self.drive(&mut alg, |_, _, _| {}) {
+            panic!("{}", violation);
         }
         alg.alg_output()
     }
 
+    fn drive<Alg, F>(&self, alg: &mut Alg, observe: F) -> Result<(), Violation>
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+        F: FnMut(usize, Option<(usize, Prob)>, Option<bool>),
+    {
+        drive(
+            self.weighted_bigraph.u_nodes.len(),
+            self.iter(),
+            alg,
+            observe,
+        )
+    }
+
+    /// The average ALG over `precision` trials. Panics if the algorithm
+    /// makes an invalid probe, `adaptive_run` reports it as a `Violation`.
+    /// `estimator::Estimator` runs the trials in parallel and also reports
+    /// their spread.
     #[allow(non_snake_case)]
-    pub fn adaptive_ALG<Alg>(self: &Self, precision: usize) -> f64
+    pub fn adaptive_ALG<Alg>(&self, precision: usize) -> f64
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo> + std::fmt::Debug,
+    {
+        self.adaptive_ALG_with_rng::<Alg>(precision, &mut StdRng::from_entropy())
+    }
+
+    /// Same as `adaptive_ALG`, but every trial is seeded from `rng`.
+    #[allow(non_snake_case)]
+    pub fn adaptive_ALG_with_rng<Alg>(&self, precision: usize, rng: &mut StdRng) -> f64
     where
         Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo> + std::fmt::Debug,
     {
         let mut alg_sum: f64 = 0.;
         for _ in 0..precision {
-            let alg = self._adaptive_alg::<Alg>();
+            let alg = self._adaptive_alg::<Alg>(rng);
             alg_sum += alg;
         }
         alg_sum / precision as f64
     }
+
+    /// The average ALG over `precision` trials in the random-order model,
+    /// every trial presents the online nodes in a fresh uniformly random
+    /// order drawn from `rng`, so it averages over both the orders and the
+    /// outcomes of the probes. Panics if the algorithm makes an invalid
+    /// probe, `random_order_adaptive_run` reports it as a `Violation`.
+    #[allow(non_snake_case)]
+    pub fn random_order_adaptive_ALG<Alg>(&self, precision: usize, rng: &mut StdRng) -> f64
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    {
+        let mut alg_sum: f64 = 0.;
+        for _ in 0..precision {
+            let order = random_order(self.weighted_bigraph.v_nodes.len(), rng);
+            let mut alg = Alg::init_with_rng(
+                self.weighted_bigraph.u_nodes.len(),
+                StdRng::seed_from_u64(rng.gen()),
+            );
+            if let Err(violation) = self.drive_in_order(&order, &mut alg, |_, _, _| {}) {
+                panic!("{}", violation);
+            }
+            alg_sum += alg.alg_output();
+        }
+        alg_sum / precision as f64
+    }
+
+    // Same as `drive`, but the online node `order[t]` arrives at time t,
+    // `observe` and the violation still get the index of the online node
+    // in the graph.
+    fn drive_in_order<Alg, F>(
+        &self,
+        order: &[usize],
+        alg: &mut Alg,
+        mut observe: F,
+    ) -> Result<(), Violation>
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+        F: FnMut(usize, Option<(usize, Prob)>, Option<bool>),
+    {
+        let online_adjacency_list = &self.weighted_bigraph.v_adjacency_list;
+        drive(
+            self.weighted_bigraph.u_nodes.len(),
+            order.iter().map(|&v| &online_adjacency_list[v]),
+            alg,
+            |t, alg_choose, success| observe(order[t], alg_choose, success),
+        )
+        .map_err(|violation| violation.in_order(order))
+    }
+
+    /// The average ALG of a non-adaptive algorithm over `precision` trials.
+    /// It commits every online node to an offline node without seeing the
+    /// outcomes of the earlier probes, so a probe of an offline node which
+    /// is already matched is wasted. Every trial samples the outcomes of
+    /// the probes, the ALG is the number of matched offline nodes.
+    /// Panics if the algorithm makes an invalid probe, `non_adaptive_run`
+    /// reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn non_adaptive_ALG<Alg>(&self, precision: usize) -> f64
+    where
+        Alg: NoneAdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    {
+        self.non_adaptive_ALG_with_rng::<Alg>(precision, &mut StdRng::from_entropy())
+    }
+
+    /// Same as `non_adaptive_ALG`, but every trial is seeded from `rng`.
+    #[allow(non_snake_case)]
+    pub fn non_adaptive_ALG_with_rng<Alg>(&self, precision: usize, rng: &mut StdRng) -> f64
+    where
+        Alg: NoneAdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    {
+        let mut alg_sum: f64 = 0.;
+        for _ in 0..precision {
+            let mut alg = Alg::init_with_rng(
+                self.weighted_bigraph.u_nodes.len(),
+                StdRng::seed_from_u64(rng.gen()),
+            );
+            let mut matched = 0;
+            let result = self.drive_non_adaptive(
+                &mut alg,
+                &mut StdRng::seed_from_u64(rng.gen()),
+                |_, _, success| {
+                    if success == Some(true) {
+                        matched += 1;
+                    }
+                },
+            );
+            if let Err(violation) = result {
+                panic!("{}", violation);
+            }
+            alg_sum += matched as f64;
+        }
+        alg_sum / precision as f64
+    }
+
+    fn drive_non_adaptive<Alg, F>(
+        &self,
+        alg: &mut Alg,
+        outcomes: &mut StdRng,
+        observe: F,
+    ) -> Result<(), Violation>
+    where
+        Alg: NoneAdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+        F: FnMut(usize, Option<(usize, Prob)>, Option<bool>),
+    {
+        drive_non_adaptive(
+            self.weighted_bigraph.u_nodes.len(),
+            self.iter(),
+            alg,
+            outcomes,
+            observe,
+        )
+    }
 }
 
-pub struct StochasticRewardIter<'a> {
-    pub online_adjacency_list: &'a Vec<Vec<(usize, Prob)>>,
-    pub online_index: usize,
+impl<Key: Copy> StochasticReward<Key> {
+    /// Runs a single trial of the algorithm and reports every probe with
+    /// its outcome and, for every offline node, the probability it has been
+    /// probed with and whether it is still unmatched,
+    /// or the first probe which isn't valid.
+    pub fn adaptive_run<Alg>(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation>
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    {
+        let order: Vec<usize> = (0..self.weighted_bigraph.v_nodes.len()).collect();
+        self.adaptive_run_in_order::<Alg>(&order, rng)
+    }
+
+    /// Same as `adaptive_run` in the random-order model, the probes are
+    /// reported in the random order the online nodes arrived in.
+    pub fn random_order_adaptive_run<Alg>(
+        &self,
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation>
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    {
+        let order = random_order(self.weighted_bigraph.v_nodes.len(), rng);
+        self.adaptive_run_in_order::<Alg>(&order, rng)
+    }
+
+    /// Runs a single trial of a non-adaptive algorithm and reports every
+    /// probe with its outcome, a probe of an already matched offline node
+    /// fails, and for every offline node the total probability it has been
+    /// probed with and whether it is still unmatched. The objective is the
+    /// number of matched offline nodes.
+    pub fn non_adaptive_run<Alg>(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation>
+    where
+        Alg: NoneAdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    {
+        let graph = &self.weighted_bigraph;
+        let mut alg = Alg::init_with_rng(graph.u_nodes.len(), StdRng::seed_from_u64(rng.gen()));
+        let mut decisions = Vec::with_capacity(graph.v_nodes.len());
+        let mut loads = vec![0.; graph.u_nodes.len()];
+        let mut available = vec![true; graph.u_nodes.len()];
+        self.drive_non_adaptive(
+            &mut alg,
+            &mut StdRng::seed_from_u64(rng.gen()),
+            |v, alg_choose, success| {
+                if let Some((u, p)) = alg_choose {
+                    loads[u] += p;
+                    if success == Some(true) {
+                        available[u] = false;
+                    }
+                }
+                decisions.push(Decision {
+                    online: graph.v_nodes[v],
+                    offline: alg_choose.map(|(u, _)| graph.u_nodes[u]),
+                    weight: alg_choose.map(|(_, p)| p),
+                    success,
+                });
+            },
+        )?;
+        let objective = available.iter().filter(|&&avail| !avail).count() as f64;
+        let offline = graph
+            .u_nodes
+            .iter()
+            .enumerate()
+            .map(|(u, &key)| OfflineState {
+                key,
+                load: loads[u],
+                available: available[u],
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective,
+        })
+    }
+
+    fn adaptive_run_in_order<Alg>(
+        &self,
+        order: &[usize],
+        rng: &mut StdRng,
+    ) -> Result<Outcome<Key>, Violation>
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    {
+        let graph = &self.weighted_bigraph;
+        let mut alg = Alg::init_with_rng(graph.u_nodes.len(), StdRng::seed_from_u64(rng.gen()));
+        let mut decisions = Vec::with_capacity(graph.v_nodes.len());
+        let mut loads = vec![0.; graph.u_nodes.len()];
+        let mut available = vec![true; graph.u_nodes.len()];
+        self.drive_in_order(order, &mut alg, |v, alg_choose, success| {
+            if let Some((u, p)) = alg_choose {
+                loads[u] += p;
+                if success == Some(true) {
+                    available[u] = false;
+                }
+            }
+            decisions.push(Decision {
+                online: graph.v_nodes[v],
+                offline: alg_choose.map(|(u, _)| graph.u_nodes[u]),
+                weight: alg_choose.map(|(_, p)| p),
+                success,
+            });
+        })?;
+        let offline = graph
+            .u_nodes
+            .iter()
+            .enumerate()
+            .map(|(u, &key)| OfflineState {
+                key,
+                load: loads[u],
+                available: available[u],
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective: alg.alg_output(),
+        })
+    }
 }
 
-impl<'a> Iterator for StochasticRewardIter<'a> {
-    type Item = &'a Vec<(usize, Prob)>;
+/// Runs a single trial of the algorithm on online nodes which aren't known
+/// in advance, every item of `arrivals` is the list of edges (with their
+/// probabilities) of the next online node.
+/// Returns the first invalid probe as a `Violation`.
+#[allow(non_snake_case)]
+pub fn stream_adaptive_ALG<Alg, I>(
+    offline_size: usize,
+    arrivals: I,
+    rng: &mut StdRng,
+) -> Result<f64, Violation>
+where
+    Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<(usize, Prob)>>,
+{
+    let mut alg = Alg::init_with_rng(offline_size, StdRng::seed_from_u64(rng.gen()));
+    drive(offline_size, arrivals, &mut alg, |_, _, _| {})?;
+    Ok(alg.alg_output())
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.online_index == self.online_adjacency_list.len() {
-            None
-        } else {
-            let t = Some(&self.online_adjacency_list[self.online_index]);
-            self.online_index += 1;
-            t
+// Feeds the online nodes to `alg` in arrival order, checks that every
+// choice is an adjacent and still unmatched offline node probed with the
+// probability of the edge, lets the algorithm probe it and reports the
+// choice and the outcome to `observe` along with the index of the
+// online node.
+fn drive<Alg, I, F>(
+    offline_size: usize,
+    arrivals: I,
+    alg: &mut Alg,
+    mut observe: F,
+) -> Result<(), Violation>
+where
+    Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<(usize, Prob)>>,
+    F: FnMut(usize, Option<(usize, Prob)>, Option<bool>),
+{
+    let mut matched = vec![false; offline_size];
+    for (v, online_adj) in arrivals.into_iter().enumerate() {
+        let online_adj = online_adj.borrow();
+        alg.next_online();
+        let alg_choose = alg.dispatch(online_adj);
+        if let Some((u, p)) = alg_choose {
+            let &(_, expected) = check_adjacent(v, u, offline_size, online_adj, |adj| adj.0)?;
+            if p != expected {
+                return Err(Violation::WrongProbability {
+                    online: v,
+                    offline: u,
+                    expected,
+                    found: p,
+                });
+            }
+            if matched[u] {
+                return Err(Violation::AlreadyMatched {
+                    online: v,
+                    offline: u,
+                });
+            }
+        }
+        let success = alg.query_success(alg_choose);
+        if let (Some((u, _)), Some(true)) = (alg_choose, success) {
+            matched[u] = true;
         }
+        observe(v, alg_choose, success);
+    }
+    Ok(())
+}
+
+// Same as `drive` for a non-adaptive algorithm: checks that every choice is
+// an adjacent offline node, matched or not, probed with the probability of
+// the edge, and samples the outcome of the probe from `outcomes` without
+// telling the algorithm. A probe of an offline node which is already
+// matched fails.
+fn drive_non_adaptive<Alg, I, F>(
+    offline_size: usize,
+    arrivals: I,
+    alg: &mut Alg,
+    outcomes: &mut StdRng,
+    mut observe: F,
+) -> Result<(), Violation>
+where
+    Alg: NoneAdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<(usize, Prob)>>,
+    F: FnMut(usize, Option<(usize, Prob)>, Option<bool>),
+{
+    let mut matched = vec![false; offline_size];
+    for (v, online_adj) in arrivals.into_iter().enumerate() {
+        let online_adj = online_adj.borrow();
+        let alg_choose = alg.dispatch(online_adj);
+        let success = match alg_choose {
+            Some((u, p)) => {
+                let &(_, expected) = check_adjacent(v, u, offline_size, online_adj, |adj| adj.0)?;
+                if p != expected {
+                    return Err(Violation::WrongProbability {
+                        online: v,
+                        offline: u,
+                        expected,
+                        found: p,
+                    });
+                }
+                // sampled even for a matched node, so that the outcomes of
+                // the later probes don't depend on it
+                let success = outcomes.gen_bool(p) && !matched[u];
+                matched[u] |= success;
+                Some(success)
+            }
+            None => None,
+        };
+        observe(v, alg_choose, success);
     }
+    Ok(())
 }
@@ -0,0 +1,186 @@
+use crate::papers::adwords::util::get_available_offline_nodes_in_weighted_onlineadj;
+use crate::papers::vertex_weighted::agkm11::psi;
+
+use super::graph::algorithm::AdaptiveAlgorithm;
+use super::graph::OfflineInfo;
+use super::graph::Prob;
+use super::weighted;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// The score of probing an offline node with load `l` with probability `p`.
+/// While l < 1 it is p * (1 - e^(l - 1)), the nodes with load at least 1
+/// come after all the others, the least loaded first.
+pub fn score(p: Prob, l: f64) -> f64 {
+    if l < 1. {
+        p * psi(l)
+    } else {
+        -l
+    }
+}
+
+/// SBALANCE of Goyal and Udwani (EC 2020) for vanishing probabilities which
+/// may differ from edge to edge. Like `mp12::Balance` it tracks the load of
+/// every offline node, the total probability it has been probed with, but
+/// it probes the available neighbour with the largest p_uv * (1 - e^(l_u - 1)),
+/// the penalty of AGKM11, instead of p_uv * e^(-l_u).
+#[derive(Debug)]
+pub struct SBalance {
+    offline_nodes_available: Vec<bool>,
+    offline_nodes_loads: Vec<Prob>,
+    rng: StdRng,
+}
+
+impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for SBalance {
+    fn init(length: OfflineInfo) -> Self {
+        Self::init_with_rng(length, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(length: OfflineInfo, rng: StdRng) -> Self {
+        SBalance {
+            offline_nodes_available: vec![true; length],
+            offline_nodes_loads: vec![0.; length],
+            rng,
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, Prob)>) -> Option<(usize, Prob)> {
+        let (u, p) = get_available_offline_nodes_in_weighted_onlineadj(
+            &self.offline_nodes_available,
+            online_adjacent,
+        )
+        .into_iter()
+        .map(|(u, p)| (u, p, score(p, self.offline_nodes_loads[u])))
+        .max_by(|u1, u2| u1.2.partial_cmp(&u2.2).unwrap())
+        .map(|(u, p, _)| (u, p))?;
+        self.offline_nodes_loads[u] += p;
+        Some((u, p))
+    }
+
+    fn query_success(&mut self, offline_node: Option<(usize, Prob)>) -> Option<bool> {
+        let (u, p) = offline_node?;
+        let result = self.rng.gen_bool(p);
+        if result {
+            self.offline_nodes_available[u] = false;
+        }
+        Some(result)
+    }
+
+    fn alg_output(self) -> f64 {
+        self.offline_nodes_available
+            .iter()
+            .filter(|&&avail| !avail)
+            .count() as f64
+    }
+}
+
+/// SBALANCE with offline weights for `weighted::WeightedStochasticReward`,
+/// it probes the available neighbour with the largest w_u * `score`, the
+/// nodes with load at least 1 still come last. Its ALG is the weight of
+/// the matched offline nodes.
+#[derive(Debug)]
+pub struct WeightedSBalance {
+    weights: Vec<f64>,
+    offline_nodes_available: Vec<bool>,
+    offline_nodes_loads: Vec<Prob>,
+    rng: StdRng,
+}
+
+impl AdaptiveAlgorithm<(usize, Prob), weighted::OfflineInfo> for WeightedSBalance {
+    fn init(weights: weighted::OfflineInfo) -> Self {
+        Self::init_with_rng(weights, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(weights: weighted::OfflineInfo, rng: StdRng) -> Self {
+        let length = weights.len();
+        WeightedSBalance {
+            weights,
+            offline_nodes_available: vec![true; length],
+            offline_nodes_loads: vec![0.; length],
+            rng,
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, Prob)>) -> Option<(usize, Prob)> {
+        let (u, p) = get_available_offline_nodes_in_weighted_onlineadj(
+            &self.offline_nodes_available,
+            online_adjacent,
+        )
+        .into_iter()
+        .map(|(u, p)| {
+            let l = self.offline_nodes_loads[u];
+            let score = if l < 1. {
+                self.weights[u] * score(p, l)
+            } else {
+                score(p, l)
+            };
+            (u, p, score)
+        })
+        .max_by(|u1, u2| u1.2.partial_cmp(&u2.2).unwrap())
+        .map(|(u, p, _)| (u, p))?;
+        self.offline_nodes_loads[u] += p;
+        Some((u, p))
+    }
+
+    fn query_success(&mut self, offline_node: Option<(usize, Prob)>) -> Option<bool> {
+        let (u, p) = offline_node?;
+        let result = self.rng.gen_bool(p);
+        if result {
+            self.offline_nodes_available[u] = false;
+        }
+        Some(result)
+    }
+
+    fn alg_output(self) -> f64 {
+        self.offline_nodes_available
+            .iter()
+            .zip(self.weights.iter())
+            .filter(|(&avail, _)| !avail)
+            .map(|(_, &weight)| weight)
+            .sum()
+    }
+}
+
+pub mod example {
+    use crate::papers::stochastic_reward::graph::StochasticReward;
+    use crate::papers::util::random_edges;
+    use crate::weightedbigraph::WBigraph;
+    use rand::{rngs::StdRng, Rng};
+
+    /// Like `mp12::from_nonweight_edges`, every online node v of `edges`
+    /// arrives as m copies, but every edge (u, v) gets its own probability
+    /// s_uv / m with s_uv drawn uniformly from [0, 2), so the probabilities
+    /// vanish as m grows without being equal.
+    pub fn heterogeneous(
+        edges: &[(usize, usize)],
+        m: usize,
+        rng: &mut StdRng,
+    ) -> StochasticReward<usize> {
+        assert!(m >= 2);
+        let scales: Vec<f64> = edges.iter().map(|_| rng.gen_range(0. ..2.)).collect();
+        let mut w_edges = Vec::new();
+        for (&(u, v), &s) in edges.iter().zip(scales.iter()) {
+            for vi in (v * m)..((v + 1) * m) {
+                w_edges.push(((u, vi), s / m as f64));
+            }
+        }
+        WBigraph::from_edges(&w_edges).into_stochastic_reward()
+    }
+
+    /// `offline_size` offline and `online_size` online nodes, every pair is
+    /// an edge with probability `density` and its probability is drawn
+    /// uniformly from (0, max_p].
+    pub fn random(
+        offline_size: usize,
+        online_size: usize,
+        density: f64,
+        max_p: f64,
+        rng: &mut StdRng,
+    ) -> StochasticReward<usize> {
+        assert!(0. < max_p && max_p <= 1.);
+        let edges: Vec<_> = random_edges(offline_size, online_size, density, rng)
+            .into_iter()
+            .map(|edge| (edge, max_p - rng.gen_range(0. ..max_p)))
+            .collect();
+        WBigraph::from_edges(&edges).into_stochastic_reward()
+    }
+}
@@ -0,0 +1,141 @@
+use crate::papers::adwords::util::get_available_offline_nodes_in_weighted_onlineadj;
+
+use super::graph::algorithm::AdaptiveAlgorithm;
+use super::graph::OfflineInfo;
+use super::graph::Prob;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::marker::PhantomData;
+
+/// The gain-sharing function f of `PrimalDual`: the share of a probe paid
+/// to the offline node when its load is `l`, the online node is paid the
+/// rest. It should be non-decreasing with e^(-l) (1 - f(l)) decreasing.
+pub trait GainSharing {
+    fn f(l: f64) -> f64;
+}
+
+/// f(l) = 1 - e^(-l), with which `PrimalDual` probes like `mp12::Balance`.
+#[derive(Debug)]
+pub struct Exponential;
+
+impl GainSharing for Exponential {
+    fn f(l: f64) -> f64 {
+        1. - f64::exp(-l)
+    }
+}
+
+/// f(l) = 1 - 0.61 e^(-0.85 l), an empirical choice of this crate and not
+/// the function of the paper: offline nodes get a share from the first
+/// probe on and it grows slower than with `Exponential`, so loaded nodes
+/// are penalized less. No ratio is proven for it, on the examples of the
+/// tests it does about as well as `Exponential`.
+#[derive(Debug)]
+pub struct Tuned;
+
+impl GainSharing for Tuned {
+    fn f(l: f64) -> f64 {
+        1. - 0.61 * f64::exp(-0.85 * l)
+    }
+}
+
+/// The structure of the online primal-dual algorithm of Huang and Zhang
+/// (STOC 2020) for vanishing probabilities which may differ from edge to
+/// edge, with a gain-sharing function f which is `Tuned` unless another
+/// `GainSharing` is given. Their ratio holds for their f only, which isn't
+/// reproduced here.
+///
+/// Every offline node u keeps its load l_u, the total probability it has
+/// been probed with, and every online node v probes the available
+/// neighbour with the largest p_uv * (1 - f(l_u)). The probe splits its
+/// probability p_uv between u and v, u is credited p_uv * f(l_u) in
+/// `alpha` and v the rest in `beta`.
+#[derive(Debug)]
+pub struct PrimalDual<F = Tuned> {
+    offline_nodes_available: Vec<bool>,
+    offline_nodes_loads: Vec<Prob>,
+    alpha: Vec<f64>,
+    beta: Vec<f64>,
+    rng: StdRng,
+    gain_sharing: PhantomData<F>,
+}
+
+impl<F> PrimalDual<F> {
+    /// The share of the probed probability credited to every offline node.
+    pub fn alpha(&self) -> &Vec<f64> {
+        &self.alpha
+    }
+
+    /// The share of the probed probability credited to every online node
+    /// so far, in arrival order, over all its probes when it has patience,
+    /// 0 for an online node which probed nothing.
+    pub fn beta(&self) -> &Vec<f64> {
+        &self.beta
+    }
+
+    /// The total credit, i.e. the probability probed so far, whose
+    /// expectation is the expected ALG. It is how the gains are split, not
+    /// a feasible dual of the configuration LP, so it certifies no ratio.
+    pub fn dual(&self) -> f64 {
+        self.alpha.iter().sum::<f64>() + self.beta.iter().sum::<f64>()
+    }
+}
+
+impl<F: GainSharing> AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for PrimalDual<F> {
+    fn init(length: OfflineInfo) -> Self {
+        Self::init_with_rng(length, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(length: OfflineInfo, rng: StdRng) -> Self {
+        PrimalDual {
+            offline_nodes_available: vec![true; length],
+            offline_nodes_loads: vec![0.; length],
+            alpha: vec![0.; length],
+            beta: vec![],
+            rng,
+            gain_sharing: PhantomData,
+        }
+    }
+
+    fn next_online(&mut self) {
+        self.beta.push(0.);
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, Prob)>) -> Option<(usize, Prob)> {
+        let loads = &self.offline_nodes_loads;
+        let probed = get_available_offline_nodes_in_weighted_onlineadj(
+            &self.offline_nodes_available,
+            online_adjacent,
+        )
+        .into_iter()
+        .max_by(|&(u1, p1), &(u2, p2)| {
+            let gain1 = p1 * (1. - F::f(loads[u1]));
+            let gain2 = p2 * (1. - F::f(loads[u2]));
+            gain1.partial_cmp(&gain2).unwrap()
+        });
+        if let Some((u, p)) = probed {
+            let share = F::f(self.offline_nodes_loads[u]);
+            self.alpha[u] += p * share;
+            *self
+                .beta
+                .last_mut()
+                .expect("next_online is called before the first probe") += p * (1. - share);
+            self.offline_nodes_loads[u] += p;
+        }
+        probed
+    }
+
+    fn query_success(&mut self, offline_node: Option<(usize, Prob)>) -> Option<bool> {
+        let (u, p) = offline_node?;
+        let result = self.rng.gen_bool(p);
+        if result {
+            self.offline_nodes_available[u] = false;
+        }
+        Some(result)
+    }
+
+    fn alg_output(self) -> f64 {
+        self.offline_nodes_available
+            .iter()
+            .filter(|&&avail| !avail)
+            .count() as f64
+    }
+}
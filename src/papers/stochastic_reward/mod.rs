@@ -1,3 +1,6 @@
 pub mod graph;
+pub mod gu20;
+pub mod hz20;
 pub mod mp12;
 pub mod ranking;
+pub mod weighted;
@@ -1,15 +1,16 @@
 use crate::papers::adwords::util::get_available_offline_nodes_in_weighted_onlineadj;
 use crate::weightedbigraph::WBigraph;
 
-use super::graph::algorithm::AdaptiveAlgorithm;
+use super::graph::algorithm::{AdaptiveAlgorithm, NoneAdaptiveAlgorithm};
 use super::graph::OfflineInfo;
 use super::graph::Prob;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 #[derive(Debug)]
 pub struct Balance {
     offline_nodes_available: Vec<bool>,
     offline_nodes_loads: Vec<Prob>,
+    rng: StdRng,
 }
 
 pub fn f(p: Prob, l: f64) -> f64 {
@@ -30,6 +31,10 @@ pub fn from_nonweight_edges(edges: &Vec<(usize, usize)>, m: usize) -> WBigraph<u
 
 impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for Balance {
     fn init(length: OfflineInfo) -> Self {
+        Self::init_with_rng(length, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(length: OfflineInfo, rng: StdRng) -> Self {
         let l = length;
         let mut offline_nodes_available = Vec::with_capacity(l);
         offline_nodes_available.resize(l, true);
@@ -38,10 +43,11 @@ impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for Balance {
         Balance {
             offline_nodes_available,
             offline_nodes_loads,
+            rng,
         }
     }
 
-    fn dispatch(self: &mut Self, online_adjacent: &Vec<(usize, Prob)>) -> Option<(usize, Prob)> {
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, Prob)>) -> Option<(usize, Prob)> {
         let available_offline_nodes = get_available_offline_nodes_in_weighted_onlineadj(
             &self.offline_nodes_available,
             online_adjacent,
@@ -66,12 +72,11 @@ impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for Balance {
         }
     }
 
-    fn query_success(self: &mut Self, offline_node: Option<(usize, Prob)>) -> Option<bool> {
+    fn query_success(&mut self, offline_node: Option<(usize, Prob)>) -> Option<bool> {
         match offline_node {
             Some(adj_info) => {
-                let mut rng = rand::thread_rng();
                 let prob = adj_info.1;
-                let result = rng.gen_bool(prob);
+                let result = self.rng.gen_bool(prob);
                 if result {
                     self.offline_nodes_available[adj_info.0] = false;
                 }
@@ -81,7 +86,7 @@ impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for Balance {
         }
     }
 
-    fn alg_output(self: Self) -> f64 {
+    fn alg_output(self) -> f64 {
         self.offline_nodes_available
             .iter()
             .map(|&avail| match avail {
@@ -92,6 +97,32 @@ impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for Balance {
     }
 }
 
+/// The non-adaptive BALANCE of MP12: it never learns which probes
+/// succeeded, so it weighs every neighbour by the total probability it has
+/// been probed with so far, whether it is still unmatched or not.
+#[derive(Debug)]
+pub struct NonAdaptiveBalance {
+    offline_nodes_loads: Vec<Prob>,
+}
+
+impl NoneAdaptiveAlgorithm<(usize, Prob), OfflineInfo> for NonAdaptiveBalance {
+    fn init(length: OfflineInfo) -> Self {
+        NonAdaptiveBalance {
+            offline_nodes_loads: vec![0.; length],
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, Prob)>) -> Option<(usize, Prob)> {
+        let (u, p) = online_adjacent
+            .iter()
+            .map(|&(u, p)| (u, p, f(p, self.offline_nodes_loads[u])))
+            .max_by(|u1, u2| u1.2.partial_cmp(&u2.2).unwrap())
+            .map(|(u, p, _)| (u, p))?;
+        self.offline_nodes_loads[u] += p;
+        Some((u, p))
+    }
+}
+
 pub mod example {
     use crate::{papers::stochastic_reward::graph::StochasticReward, weightedbigraph::WBigraph};
 
@@ -1,34 +1,39 @@
 use crate::papers::adwords::util::get_available_offline_nodes_in_weighted_onlineadj;
 
-use super::graph::algorithm::AdaptiveAlgorithm;
+use super::graph::algorithm::{AdaptiveAlgorithm, NoneAdaptiveAlgorithm};
 use super::graph::OfflineInfo;
 use super::graph::Prob;
-use rand::{thread_rng, Rng};
+use super::graph::StochasticReward;
+use super::weighted;
+use crate::papers::util::for_each_permutation;
+use crate::papers::vertex_weighted::agkm11::psi;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 #[derive(Debug)]
 pub struct Ranking {
     offline_nodes_available: Vec<bool>,
     offline_nodes_rank: Vec<i32>,
+    rng: StdRng,
 }
 
 impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for Ranking {
     fn init(lenth: OfflineInfo) -> Self {
-        use rand::seq::SliceRandom;
+        Self::init_with_rng(lenth, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(lenth: OfflineInfo, mut rng: StdRng) -> Self {
         let mut off_available = Vec::with_capacity(lenth);
         off_available.resize(lenth, true);
-        let mut rank = Vec::with_capacity(lenth);
-        for i in 0..lenth {
-            rank.push(i as i32)
-        }
-        rank.shuffle(&mut thread_rng());
+        let rank = random_ranks(lenth, &mut rng);
         Ranking {
             offline_nodes_available: off_available,
             offline_nodes_rank: rank,
+            rng,
         }
     }
 
     fn dispatch(
-        self: &mut Self,
+        &mut self,
         online_adjacent: &Vec<(usize, Prob)>,
     ) -> Option<(usize, super::graph::Prob)> {
         let available_offline_nodes = get_available_offline_nodes_in_weighted_onlineadj(
@@ -51,15 +56,11 @@ impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for Ranking {
         }
     }
 
-    fn query_success(
-        self: &mut Self,
-        offline_node: Option<(usize, super::graph::Prob)>,
-    ) -> Option<bool> {
+    fn query_success(&mut self, offline_node: Option<(usize, super::graph::Prob)>) -> Option<bool> {
         match offline_node {
             Some(adj_info) => {
-                let mut rng = rand::thread_rng();
                 let prob = adj_info.1;
-                let result = rng.gen_bool(prob);
+                let result = self.rng.gen_bool(prob);
                 if result {
                     self.offline_nodes_available[adj_info.0] = false;
                 }
@@ -69,7 +70,7 @@ impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for Ranking {
         }
     }
 
-    fn alg_output(self: Self) -> f64 {
+    fn alg_output(self) -> f64 {
         self.offline_nodes_available
             .iter()
             .map(|&avail| match avail {
@@ -79,3 +80,169 @@ impl AdaptiveAlgorithm<(usize, Prob), OfflineInfo> for Ranking {
             .sum::<i32>() as f64
     }
 }
+
+// A uniformly random rank of every offline node, the smaller the better.
+fn random_ranks(length: usize, rng: &mut StdRng) -> Vec<i32> {
+    use rand::seq::SliceRandom;
+    let mut rank: Vec<i32> = (0..length as i32).collect();
+    rank.shuffle(rng);
+    rank
+}
+
+/// RANKING without the outcomes of the probes: an offline node counts as
+/// taken once it has been probed with total probability 1, the budget of
+/// the LP benchmark, instead of once a probe succeeded.
+///
+/// When every neighbour is taken it still probes the best ranked one.
+/// This is a heuristic of this crate, not part of RANKING: a wasted probe
+/// costs a non-adaptive algorithm nothing, and `mp12::NonAdaptiveBalance`
+/// likewise probes some neighbour of every online node.
+#[derive(Debug)]
+pub struct NonAdaptiveRanking {
+    offline_nodes_loads: Vec<Prob>,
+    offline_nodes_rank: Vec<i32>,
+}
+
+impl NoneAdaptiveAlgorithm<(usize, Prob), OfflineInfo> for NonAdaptiveRanking {
+    fn init(length: OfflineInfo) -> Self {
+        Self::init_with_rng(length, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(length: OfflineInfo, mut rng: StdRng) -> Self {
+        NonAdaptiveRanking {
+            offline_nodes_loads: vec![0.; length],
+            offline_nodes_rank: random_ranks(length, &mut rng),
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, Prob)>) -> Option<(usize, Prob)> {
+        let rank = &self.offline_nodes_rank;
+        let loads = &self.offline_nodes_loads;
+        // the loads are sums of probabilities, 1 / m added m times may
+        // fall just short of 1
+        let (u, p) = online_adjacent
+            .iter()
+            .filter(|&&(u, _)| loads[u] < 1. - 1e-9)
+            .min_by_key(|&&(u, _)| rank[u])
+            .or_else(|| online_adjacent.iter().min_by_key(|&&(u, _)| rank[u]))
+            .copied()?;
+        self.offline_nodes_loads[u] += p;
+        Some((u, p))
+    }
+}
+
+/// RANKING with offline weights for `weighted::WeightedStochasticReward`,
+/// the Perturbed Greedy of AGKM11 with the probabilities of the edges:
+/// every offline node u draws y_u uniformly from [0, 1] at `init` and
+/// every probe goes to the available neighbour with the largest
+/// w_u * p_uv * (1 - e^(y_u - 1)). Its ALG is the weight of the matched
+/// offline nodes.
+#[derive(Debug)]
+pub struct PerturbedRanking {
+    weights: Vec<f64>,
+    offline_nodes_available: Vec<bool>,
+    perturbed_weights: Vec<f64>,
+    rng: StdRng,
+}
+
+impl AdaptiveAlgorithm<(usize, Prob), weighted::OfflineInfo> for PerturbedRanking {
+    fn init(weights: weighted::OfflineInfo) -> Self {
+        Self::init_with_rng(weights, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(weights: weighted::OfflineInfo, mut rng: StdRng) -> Self {
+        let perturbed_weights = weights
+            .iter()
+            .map(|&w| w * psi(rng.gen_range(0. ..1.)))
+            .collect();
+        PerturbedRanking {
+            offline_nodes_available: vec![true; weights.len()],
+            weights,
+            perturbed_weights,
+            rng,
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<(usize, Prob)>) -> Option<(usize, Prob)> {
+        get_available_offline_nodes_in_weighted_onlineadj(
+            &self.offline_nodes_available,
+            online_adjacent,
+        )
+        .into_iter()
+        .max_by(|&(u1, p1), &(u2, p2)| {
+            let score1 = p1 * self.perturbed_weights[u1];
+            let score2 = p2 * self.perturbed_weights[u2];
+            score1.partial_cmp(&score2).unwrap()
+        })
+    }
+
+    fn query_success(&mut self, offline_node: Option<(usize, Prob)>) -> Option<bool> {
+        let (u, p) = offline_node?;
+        let result = self.rng.gen_bool(p);
+        if result {
+            self.offline_nodes_available[u] = false;
+        }
+        Some(result)
+    }
+
+    fn alg_output(self) -> f64 {
+        self.offline_nodes_available
+            .iter()
+            .zip(self.weights.iter())
+            .filter(|(&avail, _)| !avail)
+            .map(|(_, &weight)| weight)
+            .sum()
+    }
+}
+
+/// `exact_ranking_ALG` goes through every permutation of the offline nodes
+/// and every subset of them, so it refuses instances with more offline
+/// nodes than this.
+pub const MAX_EXACT_RANKING_OFFLINE_SIZE: usize = 8;
+
+impl<Key> StochasticReward<Key> {
+    /// The exact expected ALG of `Ranking`, over all the n! rank
+    /// permutations of the n offline nodes and all the outcomes of the
+    /// probes instead of sampled ones.
+    #[allow(non_snake_case)]
+    pub fn exact_ranking_ALG(&self) -> f64 {
+        let offline_size = self.weighted_bigraph.u_nodes.len();
+        assert!(
+            offline_size <= MAX_EXACT_RANKING_OFFLINE_SIZE,
+            "exact_ranking_ALG supports at most {} offline nodes, got {}",
+            MAX_EXACT_RANKING_OFFLINE_SIZE,
+            offline_size
+        );
+        let mut alg_sum = 0.;
+        let mut permutations = 0;
+        for_each_permutation(offline_size, |rank| {
+            // reach[mask] is the probability that exactly the offline nodes
+            // in mask are still available
+            let mut reach = vec![0.; 1 << offline_size];
+            reach[(1 << offline_size) - 1] = 1.;
+            for online_adj in self.weighted_bigraph.v_adjacency_list.iter() {
+                let mut next = vec![0.; reach.len()];
+                for (mask, &q) in reach.iter().enumerate() {
+                    if q == 0. {
+                        continue;
+                    }
+                    let probed = online_adj
+                        .iter()
+                        .filter(|&&(u, _)| mask & (1 << u) != 0)
+                        .min_by_key(|&&(u, _)| rank[u]);
+                    match probed {
+                        Some(&(u, p)) => {
+                            alg_sum += q * p;
+                            next[mask ^ (1 << u)] += q * p;
+                            next[mask] += q * (1. - p);
+                        }
+                        None => next[mask] += q,
+                    }
+                }
+                reach = next;
+            }
+            permutations += 1;
+        });
+        alg_sum / permutations as f64
+    }
+}
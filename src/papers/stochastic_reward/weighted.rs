@@ -0,0 +1,332 @@
+use crate::error::Error;
+use crate::offline::allocation::stochastic_matching_lp;
+use crate::papers::instance::{ArrivalIter, Instance, Simulate};
+use crate::papers::outcome::{Decision, OfflineState, Outcome};
+use crate::papers::validation::{check_adjacent, Violation};
+use crate::papers::vertex_weighted::graph::FromVertexWeights;
+use crate::weightedbigraph::WBigraph;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::borrow::Borrow;
+
+use super::graph::algorithm::AdaptiveAlgorithm;
+use super::graph::Prob;
+
+/// The weights of the offline nodes.
+pub type OfflineInfo = Vec<f64>;
+
+impl<Key: Copy> WBigraph<Key, Prob> {
+    /// Gives every offline node a weight, `weights[i]` for `u_nodes[i]`,
+    /// gained when a probe of it succeeds, and every online node the
+    /// patience to be probed up to `patience` times.
+    /// Panics on an invalid probability, weight or patience,
+    /// `try_into_weighted_stochastic_reward` returns an `Error` instead.
+    pub fn into_weighted_stochastic_reward(
+        self,
+        weights: Vec<f64>,
+        patience: usize,
+    ) -> WeightedStochasticReward<Key>
+    where
+        Key: std::fmt::Debug,
+    {
+        self.try_into_weighted_stochastic_reward(weights, patience)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_into_weighted_stochastic_reward(
+        self,
+        weights: Vec<f64>,
+        patience: usize,
+    ) -> Result<WeightedStochasticReward<Key>, Error<Key>> {
+        let weighted_bigraph = self.try_into_stochastic_reward()?.weighted_bigraph;
+        if weights.len() != weighted_bigraph.u_nodes.len() {
+            return Err(Error::WeightLengthMismatch {
+                offline_size: weighted_bigraph.u_nodes.len(),
+                weights: weights.len(),
+            });
+        }
+        for (u, &weight) in weights.iter().enumerate() {
+            if !(weight >= 0. && weight.is_finite()) {
+                return Err(Error::InvalidWeight {
+                    offline: weighted_bigraph.u_nodes[u],
+                    weight,
+                });
+            }
+        }
+        if patience == 0 {
+            return Err(Error::InvalidPatience { patience });
+        }
+        Ok(WeightedStochasticReward {
+            weighted_bigraph,
+            weights,
+            patience,
+        })
+    }
+}
+
+/// Stochastic rewards with offline weights and online patience: every
+/// online node is offered to its neighbours one at a time, until a probe
+/// succeeds, the algorithm lets it go or it has been probed `patience`
+/// times. A successful probe of u gains the weight of u.
+#[derive(Debug)]
+pub struct WeightedStochasticReward<Key> {
+    pub weighted_bigraph: WBigraph<Key, Prob>,
+    weights: Vec<f64>,
+    patience: usize,
+}
+
+impl<Key> Instance for WeightedStochasticReward<Key> {
+    type Key = Key;
+    type AdjType = (usize, Prob);
+    type OfflineInfo = OfflineInfo;
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.weighted_bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.weighted_bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> OfflineInfo {
+        self.weights.clone()
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, (usize, Prob)> {
+        ArrivalIter::new(&self.weighted_bigraph.v_adjacency_list)
+    }
+
+    /// The LP benchmark, see `WeightedStochasticReward::OPT`.
+    fn OPT(&self) -> f64 {
+        WeightedStochasticReward::OPT(self)
+    }
+}
+
+impl<Key, Alg> Simulate<Alg> for WeightedStochasticReward<Key>
+where
+    Key: Copy,
+    Alg: AdaptiveAlgorithm<(usize, Prob), OfflineInfo>,
+{
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation> {
+        self.run::<Alg, OfflineInfo>(rng)
+    }
+}
+
+impl<Key> WeightedStochasticReward<Key> {
+    pub fn weights(&self) -> &Vec<f64> {
+        &self.weights
+    }
+
+    pub fn patience(&self) -> usize {
+        self.patience
+    }
+
+    /// The LP benchmark, an upper bound of the expected reward of the best
+    /// adaptive policy, see `offline::allocation::stochastic_matching_lp`.
+    /// With unit weights and patience 1 it is `StochasticReward::OPT`.
+    #[allow(non_snake_case)]
+    pub fn OPT(&self) -> f64 {
+        stochastic_matching_lp(
+            &self.weights,
+            self.patience,
+            &self.weighted_bigraph.v_adjacency_list,
+        )
+    }
+
+    /// The average ALG over `precision` trials. The weighted algorithms
+    /// report the weight of the matched offline nodes, the unweighted ones
+    /// their number. Panics if the algorithm makes an invalid probe, `run`
+    /// reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn ALG<Alg, Info>(&self, precision: usize) -> f64
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), Info>,
+        Info: FromVertexWeights,
+    {
+        self.ALG_with_rng::<Alg, Info>(precision, &mut StdRng::from_entropy())
+    }
+
+    /// Same as `ALG`, but every trial is seeded from `rng`.
+    #[allow(non_snake_case)]
+    pub fn ALG_with_rng<Alg, Info>(&self, precision: usize, rng: &mut StdRng) -> f64
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), Info>,
+        Info: FromVertexWeights,
+    {
+        let mut alg_sum: f64 = 0.;
+        for _ in 0..precision {
+            let mut alg = Alg::init_with_rng(
+                Info::from_vertex_weights(&self.weights),
+                StdRng::seed_from_u64(rng.gen()),
+            );
+            if let Err(violation) = self.drive(&mut alg, |_, _, _| {}) {
+                panic!("{}", violation);
+            }
+            alg_sum += alg.alg_output();
+        }
+        alg_sum / precision as f64
+    }
+
+    fn drive<Alg, Info, F>(&self, alg: &mut Alg, observe: F) -> Result<(), Violation>
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), Info>,
+        F: FnMut(usize, Option<(usize, Prob)>, Option<bool>),
+    {
+        drive(
+            self.weighted_bigraph.u_nodes.len(),
+            self.patience,
+            &self.weighted_bigraph.v_adjacency_list,
+            alg,
+            observe,
+        )
+    }
+}
+
+impl<Key: Copy> WeightedStochasticReward<Key> {
+    /// Runs a single trial of the algorithm and reports every probe with
+    /// its outcome, so an online node has a decision for each time it was
+    /// probed, or a single decision without an offline node if it wasn't
+    /// probed at all. For every offline node it reports the probability it
+    /// has been probed with and whether it is still unmatched.
+    /// The objective is what the algorithm reports.
+    pub fn run<Alg, Info>(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation>
+    where
+        Alg: AdaptiveAlgorithm<(usize, Prob), Info>,
+        Info: FromVertexWeights,
+    {
+        let graph = &self.weighted_bigraph;
+        let mut alg = Alg::init_with_rng(
+            Info::from_vertex_weights(&self.weights),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+        let mut decisions = Vec::with_capacity(graph.v_nodes.len());
+        let mut loads = vec![0.; graph.u_nodes.len()];
+        let mut available = vec![true; graph.u_nodes.len()];
+        self.drive(&mut alg, |v, alg_choose, success| {
+            if let Some((u, p)) = alg_choose {
+                loads[u] += p;
+                if success == Some(true) {
+                    available[u] = false;
+                }
+            }
+            decisions.push(Decision {
+                online: graph.v_nodes[v],
+                offline: alg_choose.map(|(u, _)| graph.u_nodes[u]),
+                weight: alg_choose.map(|(_, p)| p),
+                success,
+            });
+        })?;
+        let offline = graph
+            .u_nodes
+            .iter()
+            .enumerate()
+            .map(|(u, &key)| OfflineState {
+                key,
+                load: loads[u],
+                available: available[u],
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective: alg.alg_output(),
+        })
+    }
+}
+
+// Offers every online node to `alg`, after `next_online`, with the edges
+// it hasn't been probed on yet, until a probe succeeds, `alg` returns None
+// or the patience runs out. Checks every probe like `graph::drive` and
+// that no edge is probed twice, and reports every probe and its outcome to
+// `observe` along with the index of the online node. An online node which isn't probed at all
+// is reported once without a probe.
+fn drive<Alg, Info, I, F>(
+    offline_size: usize,
+    patience: usize,
+    arrivals: I,
+    alg: &mut Alg,
+    mut observe: F,
+) -> Result<(), Violation>
+where
+    Alg: AdaptiveAlgorithm<(usize, Prob), Info>,
+    I: IntoIterator,
+    I::Item: Borrow<Vec<(usize, Prob)>>,
+    F: FnMut(usize, Option<(usize, Prob)>, Option<bool>),
+{
+    let mut matched = vec![false; offline_size];
+    for (v, online_adj) in arrivals.into_iter().enumerate() {
+        let online_adj = online_adj.borrow();
+        let mut offered = online_adj.clone();
+        alg.next_online();
+        for probes in 0..patience {
+            let (u, p) = match alg.dispatch(&offered) {
+                Some(alg_choose) => alg_choose,
+                None => {
+                    if probes == 0 {
+                        observe(v, None, None);
+                    }
+                    break;
+                }
+            };
+            if !offered.iter().any(|&(w, _)| w == u) && online_adj.iter().any(|&(w, _)| w == u) {
+                return Err(Violation::AlreadyProbed {
+                    online: v,
+                    offline: u,
+                });
+            }
+            let &(_, expected) = check_adjacent(v, u, offline_size, &offered, |adj| adj.0)?;
+            if p != expected {
+                return Err(Violation::WrongProbability {
+                    online: v,
+                    offline: u,
+                    expected,
+                    found: p,
+                });
+            }
+            if matched[u] {
+                return Err(Violation::AlreadyMatched {
+                    online: v,
+                    offline: u,
+                });
+            }
+            let success = alg.query_success(Some((u, p)));
+            observe(v, Some((u, p)), success);
+            if success == Some(true) {
+                matched[u] = true;
+                break;
+            }
+            offered.retain(|&(w, _)| w != u);
+        }
+    }
+    Ok(())
+}
+
+pub mod example {
+    use super::WeightedStochasticReward;
+    use crate::papers::util::random_edges;
+    use crate::weightedbigraph::WBigraph;
+    use rand::{rngs::StdRng, Rng};
+
+    /// `offline_size` offline and `online_size` online nodes, every pair is
+    /// an edge with probability `density`, the probabilities of the edges
+    /// are drawn uniformly from (0, 1] and the weights of the offline nodes
+    /// from [1, 10).
+    pub fn random(
+        offline_size: usize,
+        online_size: usize,
+        density: f64,
+        patience: usize,
+        rng: &mut StdRng,
+    ) -> WeightedStochasticReward<usize> {
+        let edges: Vec<_> = random_edges(offline_size, online_size, density, rng)
+            .into_iter()
+            .map(|edge| (edge, 1. - rng.gen_range(0. ..1.)))
+            .collect();
+        let graph = WBigraph::from_edges(&edges);
+        let weights = graph
+            .u_nodes
+            .iter()
+            .map(|_| rng.gen_range(1. ..10.))
+            .collect();
+        graph.into_weighted_stochastic_reward(weights, patience)
+    }
+}
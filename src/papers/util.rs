@@ -1,6 +1,8 @@
+use rand::{rngs::StdRng, Rng};
+
 pub fn get_available_offline_nodes_in_onlineadj(
-    offline_nodes_available: &Vec<bool>,
-    online_adjacent: &Vec<usize>,
+    offline_nodes_available: &[bool],
+    online_adjacent: &[usize],
 ) -> Vec<usize> {
     let mut vec = Vec::with_capacity(online_adjacent.len());
     for &offline_node in online_adjacent.iter() {
@@ -10,3 +12,67 @@ pub fn get_available_offline_nodes_in_onlineadj(
     }
     vec
 }
+
+/// The neighbour which has used the smallest fraction of its capacity,
+/// among those with some capacity left, the first one on ties.
+pub fn least_used(
+    online_adjacent: &[usize],
+    loads: &[usize],
+    capacities: &[usize],
+) -> Option<usize> {
+    let mut best: Option<(usize, f64)> = None;
+    for &u in online_adjacent.iter() {
+        if loads[u] == capacities[u] {
+            continue;
+        }
+        let used = loads[u] as f64 / capacities[u] as f64;
+        if best.map_or(true, |(_, best_used)| used < best_used) {
+            best = Some((u, used));
+        }
+    }
+    best.map(|(u, _)| u)
+}
+
+/// Calls `f` on every permutation of 0..n, by Heap's algorithm.
+pub fn for_each_permutation<F: FnMut(&Vec<usize>)>(n: usize, mut f: F) {
+    let mut permutation: Vec<usize> = (0..n).collect();
+    let mut counter = vec![0; n];
+    f(&permutation);
+    let mut i = 1;
+    while i < n {
+        if counter[i] < i {
+            if i % 2 == 0 {
+                permutation.swap(0, i);
+            } else {
+                permutation.swap(counter[i], i);
+            }
+            f(&permutation);
+            counter[i] += 1;
+            i = 1;
+        } else {
+            counter[i] = 0;
+            i += 1;
+        }
+    }
+}
+
+/// The edges (u, v) of a random graph on `offline_size` offline and
+/// `online_size` online nodes in which every pair is an edge with
+/// probability `density`, by arrival of v. The random examples of the
+/// models attach probabilities, weights or durations to them.
+pub fn random_edges(
+    offline_size: usize,
+    online_size: usize,
+    density: f64,
+    rng: &mut StdRng,
+) -> Vec<(usize, usize)> {
+    let mut edges = Vec::new();
+    for v in 0..online_size {
+        for u in 0..offline_size {
+            if rng.gen_bool(density) {
+                edges.push((u, v));
+            }
+        }
+    }
+    edges
+}
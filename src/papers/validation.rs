@@ -0,0 +1,137 @@
+use std::fmt;
+
+/// A decision of an online algorithm which breaks the rules of the model.
+/// `online` is the index of the online node in the graph (its arrival index
+/// in a stream) and `offline` the index of the offline node the algorithm
+/// chose for it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// There is no offline node with this index.
+    UnknownOffline { online: usize, offline: usize },
+    /// The offline node isn't adjacent to the online node.
+    NotAdjacent { online: usize, offline: usize },
+    /// The offline node is already matched.
+    AlreadyMatched { online: usize, offline: usize },
+    /// The bids assigned to the offline node already spend its whole budget.
+    BudgetExhausted { online: usize, offline: usize },
+    /// The reusable offline node is in use until the arrival `available_at`.
+    Busy {
+        online: usize,
+        offline: usize,
+        available_at: usize,
+    },
+    /// The probability the algorithm probed with isn't the one of the edge.
+    WrongProbability {
+        online: usize,
+        offline: usize,
+        expected: f64,
+        found: f64,
+    },
+    /// The fractions the online node is split into are negative or add up
+    /// to more than 1.
+    InvalidFraction { online: usize, total: f64 },
+    /// The fractions assigned to the offline node exceed its capacity.
+    CapacityExceeded { online: usize, offline: usize },
+    /// The online node has already been offered to the offline node.
+    AlreadyProbed { online: usize, offline: usize },
+}
+
+impl Violation {
+    // Turns the arrival time t of a random-order run into the online node
+    // `order[t]` which arrived then.
+    pub(crate) fn in_order(mut self, order: &[usize]) -> Self {
+        match &mut self {
+            Violation::UnknownOffline { online, .. }
+            | Violation::NotAdjacent { online, .. }
+            | Violation::AlreadyMatched { online, .. }
+            | Violation::BudgetExhausted { online, .. }
+            | Violation::Busy { online, .. }
+            | Violation::WrongProbability { online, .. }
+            | Violation::InvalidFraction { online, .. }
+            | Violation::CapacityExceeded { online, .. }
+            | Violation::AlreadyProbed { online, .. } => *online = order[*online],
+        }
+        self
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::UnknownOffline { online, offline } => write!(
+                f,
+                "online node {} is matched to the unknown offline node {}",
+                online, offline
+            ),
+            Violation::NotAdjacent { online, offline } => write!(
+                f,
+                "online node {} is matched to the non adjacent offline node {}",
+                online, offline
+            ),
+            Violation::AlreadyMatched { online, offline } => write!(
+                f,
+                "online node {} is matched to the already matched offline node {}",
+                online, offline
+            ),
+            Violation::BudgetExhausted { online, offline } => write!(
+                f,
+                "online node {} is assigned to offline node {} whose budget is exhausted",
+                online, offline
+            ),
+            Violation::Busy {
+                online,
+                offline,
+                available_at,
+            } => write!(
+                f,
+                "online node {} is matched to offline node {} which is in use until {}",
+                online, offline, available_at
+            ),
+            Violation::WrongProbability {
+                online,
+                offline,
+                expected,
+                found,
+            } => write!(
+                f,
+                "online node {} probes offline node {} with probability {} instead of {}",
+                online, offline, found, expected
+            ),
+            Violation::InvalidFraction { online, total } => write!(
+                f,
+                "online node {} is split into fractions adding up to {}",
+                online, total
+            ),
+            Violation::CapacityExceeded { online, offline } => write!(
+                f,
+                "online node {} is assigned to offline node {} whose capacity is exhausted",
+                online, offline
+            ),
+            Violation::AlreadyProbed { online, offline } => write!(
+                f,
+                "online node {} probes offline node {} a second time",
+                online, offline
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+/// Checks that `offline` exists and is adjacent to the online node,
+/// returns its entry in the adjacency list.
+pub fn check_adjacent<AdjType>(
+    online: usize,
+    offline: usize,
+    offline_size: usize,
+    online_adjacent: &[AdjType],
+    offline_of: impl Fn(&AdjType) -> usize,
+) -> Result<&AdjType, Violation> {
+    if offline >= offline_size {
+        return Err(Violation::UnknownOffline { online, offline });
+    }
+    online_adjacent
+        .iter()
+        .find(|adj| offline_of(adj) == offline)
+        .ok_or(Violation::NotAdjacent { online, offline })
+}
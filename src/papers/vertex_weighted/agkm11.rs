@@ -0,0 +1,62 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::papers::algorithm::algorithm::OnlineAlgorithm;
+
+type OfflineInfo = Vec<f64>;
+
+/// The Perturbed Greedy algorithm of Aggarwal, Goel, Karande and Mehta
+/// (SODA 2011), (1 - 1/e)-competitive for vertex-weighted matching.
+///
+/// Every offline node u draws y_u uniformly from [0, 1] at `init` and
+/// every online node is matched to the available neighbour with the
+/// largest perturbed weight w_u * (1 - e^(y_u - 1)). With equal weights
+/// it is Ranking.
+pub struct PerturbedGreedy {
+    offline_nodes_available: Vec<bool>,
+    weights: Vec<f64>,
+    perturbed_weights: Vec<f64>,
+    alg: f64,
+}
+
+/// The perturbation 1 - e^(y - 1) of AGKM11.
+pub fn psi(y: f64) -> f64 {
+    1. - f64::exp(y - 1.)
+}
+
+impl OnlineAlgorithm<usize, OfflineInfo> for PerturbedGreedy {
+    fn init(weights: OfflineInfo) -> Self {
+        Self::init_with_rng(weights, StdRng::from_entropy())
+    }
+
+    fn init_with_rng(weights: OfflineInfo, mut rng: StdRng) -> Self {
+        let perturbed_weights = weights.iter().map(|w| w * psi(rng.gen::<f64>())).collect();
+        PerturbedGreedy {
+            offline_nodes_available: vec![true; weights.len()],
+            weights,
+            perturbed_weights,
+            alg: 0.,
+        }
+    }
+
+    fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        for &u in online_adjacent.iter() {
+            if !self.offline_nodes_available[u] {
+                continue;
+            }
+            if best.map_or(true, |b| {
+                self.perturbed_weights[u] > self.perturbed_weights[b]
+            }) {
+                best = Some(u);
+            }
+        }
+        let u = best?;
+        self.offline_nodes_available[u] = false;
+        self.alg += self.weights[u];
+        Some(u)
+    }
+
+    fn alg_output(self) -> f64 {
+        self.alg
+    }
+}
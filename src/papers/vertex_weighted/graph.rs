@@ -0,0 +1,236 @@
+use crate::bigraph::Bigraph;
+use crate::error::Error;
+use crate::offline::matching::vertex_weighted_matching;
+use crate::papers::algorithm::{algorithm::OnlineAlgorithm, drive};
+use crate::papers::instance::{ArrivalIter, Instance, Simulate};
+use crate::papers::outcome::{Decision, OfflineState, Outcome};
+use crate::papers::validation::Violation;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+impl<Key: Copy> Bigraph<Key> {
+    /// Gives every offline node a weight, `weights[i]` for `u_nodes[i]`,
+    /// which is gained when it is matched.
+    /// Panics on invalid weights, `try_into_vertex_weighted` returns an
+    /// `Error` instead.
+    pub fn into_vertex_weighted(self, weights: Vec<f64>) -> VertexWeighted<Key>
+    where
+        Key: std::fmt::Debug,
+    {
+        self.try_into_vertex_weighted(weights)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_into_vertex_weighted(
+        self,
+        weights: Vec<f64>,
+    ) -> Result<VertexWeighted<Key>, Error<Key>> {
+        if weights.len() != self.u_nodes.len() {
+            return Err(Error::WeightLengthMismatch {
+                offline_size: self.u_nodes.len(),
+                weights: weights.len(),
+            });
+        }
+        for (u, &weight) in weights.iter().enumerate() {
+            if !(weight >= 0. && weight.is_finite()) {
+                return Err(Error::InvalidWeight {
+                    offline: self.u_nodes[u],
+                    weight,
+                });
+            }
+        }
+        Ok(VertexWeighted {
+            bigraph: self,
+            weights,
+        })
+    }
+}
+
+/// The vertex-weighted model of AGKM11: the same rules as the unweighted
+/// model, but matching an offline node gains its weight.
+#[derive(Debug)]
+pub struct VertexWeighted<Key> {
+    bigraph: Bigraph<Key>,
+    weights: Vec<f64>,
+}
+
+/// How an algorithm's `OfflineInfo` is built from the offline weights,
+/// so that both the unweighted algorithms (which only get the number of
+/// offline nodes) and the weighted ones can run on the model.
+pub trait FromVertexWeights {
+    fn from_vertex_weights(weights: &[f64]) -> Self;
+}
+
+impl FromVertexWeights for usize {
+    fn from_vertex_weights(weights: &[f64]) -> Self {
+        weights.len()
+    }
+}
+
+impl FromVertexWeights for Vec<f64> {
+    fn from_vertex_weights(weights: &[f64]) -> Self {
+        weights.to_vec()
+    }
+}
+
+impl<Key> Instance for VertexWeighted<Key> {
+    type Key = Key;
+    type AdjType = usize;
+    type OfflineInfo = Vec<f64>;
+
+    fn offline_keys(&self) -> &Vec<Key> {
+        &self.bigraph.u_nodes
+    }
+
+    fn online_keys(&self) -> &Vec<Key> {
+        &self.bigraph.v_nodes
+    }
+
+    fn offline_info(&self) -> Vec<f64> {
+        self.weights.clone()
+    }
+
+    fn arrivals(&self) -> ArrivalIter<'_, usize> {
+        ArrivalIter::new(&self.bigraph.v_adjacency_list)
+    }
+
+    fn OPT(&self) -> f64 {
+        VertexWeighted::OPT(self)
+    }
+}
+
+impl<Key, Alg> Simulate<Alg> for VertexWeighted<Key>
+where
+    Key: Copy,
+    Alg: OnlineAlgorithm<usize, Vec<f64>>,
+{
+    fn simulate(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation> {
+        self.run::<Alg, Vec<f64>>(rng)
+    }
+}
+
+impl<Key> VertexWeighted<Key> {
+    pub fn weights(&self) -> &Vec<f64> {
+        &self.weights
+    }
+
+    /// The offline optimum, i.e. the weight of the maximum weight matching.
+    #[allow(non_snake_case)]
+    pub fn OPT(&self) -> f64 {
+        self.opt_matching().0
+    }
+
+    /// The matching behind `OPT`, returns its weight and the offline node
+    /// matched to every online node (in arrival order).
+    pub fn opt_matching(&self) -> (f64, Vec<Option<usize>>) {
+        vertex_weighted_matching(&self.weights, &self.bigraph.v_adjacency_list)
+    }
+
+    /// Panics if the algorithm makes an invalid decision,
+    /// `run` reports it as a `Violation` instead.
+    #[allow(non_snake_case)]
+    pub fn ALG<Alg, Info>(&self) -> f64
+    where
+        Alg: OnlineAlgorithm<usize, Info>,
+        Info: FromVertexWeights,
+    {
+        self.ALG_with_rng::<Alg, Info>(&mut StdRng::from_entropy())
+    }
+
+    /// Same as `ALG`, but the algorithm draws its randomness from `rng`.
+    #[allow(non_snake_case)]
+    pub fn ALG_with_rng<Alg, Info>(&self, rng: &mut StdRng) -> f64
+    where
+        Alg: OnlineAlgorithm<usize, Info>,
+        Info: FromVertexWeights,
+    {
+        let mut alg = Alg::init_with_rng(
+            Info::from_vertex_weights(&self.weights),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+        if let Err(violation) = self.drive(&mut alg, |_, _| {}) {
+            panic!("{}", violation);
+        }
+        alg.alg_output()
+    }
+
+    fn drive<Alg, Info, F>(&self, alg: &mut Alg, observe: F) -> Result<(), Violation>
+    where
+        Alg: OnlineAlgorithm<usize, Info>,
+        F: FnMut(usize, Option<usize>),
+    {
+        drive(
+            self.bigraph.u_nodes.len(),
+            &self.bigraph.v_adjacency_list,
+            alg,
+            observe,
+        )
+    }
+}
+
+impl<Key: Copy> VertexWeighted<Key> {
+    /// Runs the algorithm once and reports every decision, with the weight
+    /// of the chosen offline node, and the final state of the offline
+    /// nodes, or the first decision which isn't a valid matching.
+    /// The objective is what the algorithm reports, for the unweighted
+    /// algorithms the number of matches.
+    pub fn run<Alg, Info>(&self, rng: &mut StdRng) -> Result<Outcome<Key>, Violation>
+    where
+        Alg: OnlineAlgorithm<usize, Info>,
+        Info: FromVertexWeights,
+    {
+        let v_nodes = &self.bigraph.v_nodes;
+        let u_nodes = &self.bigraph.u_nodes;
+        let mut alg = Alg::init_with_rng(
+            Info::from_vertex_weights(&self.weights),
+            StdRng::seed_from_u64(rng.gen()),
+        );
+        let mut decisions = Vec::with_capacity(v_nodes.len());
+        let mut loads = vec![0.; u_nodes.len()];
+        self.drive(&mut alg, |v, alg_choose| {
+            if let Some(u) = alg_choose {
+                loads[u] += 1.;
+            }
+            decisions.push(Decision {
+                online: v_nodes[v],
+                offline: alg_choose.map(|u| u_nodes[u]),
+                weight: alg_choose.map(|u| self.weights[u]),
+                success: None,
+            });
+        })?;
+        let offline = u_nodes
+            .iter()
+            .zip(loads)
+            .map(|(&key, load)| OfflineState {
+                key,
+                load,
+                available: load == 0.,
+            })
+            .collect();
+        Ok(Outcome {
+            decisions,
+            offline,
+            objective: alg.alg_output(),
+        })
+    }
+}
+
+pub mod example {
+    use super::VertexWeighted;
+    use crate::bigraph::Bigraph;
+
+    /// The upper triangular graph of `kvv90::example::ranking_worst_case`
+    /// with `weights.len()` offline and online nodes: the online node i is
+    /// adjacent to the offline nodes i, ..., n - 1, the offline node u
+    /// weighs `weights[u]`. With equal weights no online algorithm is
+    /// better than 1 - 1/e on it, so neither is Perturbed Greedy.
+    pub fn upper_triangular(weights: Vec<f64>) -> VertexWeighted<usize> {
+        let n = weights.len();
+        let mut edges = Vec::new();
+        for v in 0..n {
+            for u in v..n {
+                edges.push((u, v));
+            }
+        }
+        Bigraph::from_edges(&edges).into_vertex_weighted(weights)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod agkm11;
+pub mod graph;
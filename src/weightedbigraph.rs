@@ -1,7 +1,13 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::adjacency::{key_index, GraphIndex};
+use crate::error::{Error, Side};
 
 type Edge<Key> = (Key, Key);
 
+type WeightedIndex<'a, Key, Weight> =
+    GraphIndex<'a, Key, (Edge<Key>, Weight), ((usize, usize), Weight), (usize, Weight)>;
+
 #[derive(Debug, PartialEq)]
 pub struct WBigraph<Key, Weight> {
     pub v_nodes: Vec<Key>,
@@ -14,6 +20,16 @@ pub struct WBigraph<Key, Weight> {
     pub u_adjacency_list: Vec<Vec<(usize, Weight)>>,
 }
 
+impl<Key, Weight> Default for WBigraph<Key, Weight>
+where
+    Key: Ord + Copy + std::fmt::Debug,
+    Weight: PartialOrd + Copy + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<Key, Weight> WBigraph<Key, Weight>
 where
     Key: Ord + Copy + std::fmt::Debug,
@@ -32,67 +48,120 @@ where
         }
     }
 
+    /// Panics on a duplicate edge, the same pair of nodes with the same
+    /// weight, `try_from_edges` returns it as an `Error` instead. Of the
+    /// same pair with different weights only the heaviest edge is kept, at
+    /// the place of the first one, so there is at most one edge per pair.
+    /// Takes O(m log n) time for m edges and n nodes.
     pub fn from_edges(edges: &Vec<(Edge<Key>, Weight)>) -> Self {
+        Self::try_from_edges(edges).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_edges(edges: &Vec<(Edge<Key>, Weight)>) -> Result<Self, Error<Key>> {
         let mut graph = Self::new();
-        for edge in edges {
-            assert!(
-                !graph.nodes_edges.contains(edge),
-                "edges should't contain the same edge: {:?}",
-                edge
-            );
-            let ((ref u, ref v), w) = edge;
-            let w = w.clone();
-
-            let v_index;
-            if !graph.v_nodes.contains(v) {
-                v_index = graph.v_nodes.len();
-                graph.v_key2index.insert(v.clone(), v_index);
-                graph.v_nodes.push(v.clone());
-                graph.v_adjacency_list.push(vec![]);
-            } else {
-                v_index = graph.v_key2index[v];
+        // the position of every index pair in `kept` and the weights seen
+        // for it, so that a duplicate is found without scanning all the edges
+        let mut seen: HashMap<(usize, usize), (usize, Vec<Weight>)> =
+            HashMap::with_capacity(edges.len());
+        let mut kept: Vec<(Edge<Key>, Weight)> = Vec::with_capacity(edges.len());
+        let mut index = graph.index();
+        for &((u, v), w) in edges {
+            let v_index = index.v_index_or_insert(v);
+            let u_index = index.u_index_or_insert(u);
+            let (position, weights) = seen
+                .entry((u_index, v_index))
+                .or_insert((kept.len(), Vec::new()));
+            if weights.contains(&w) {
+                return Err(Error::DuplicateEdge {
+                    offline: u,
+                    online: v,
+                });
             }
-
-            let u_index;
-            if !graph.u_nodes.contains(u) {
-                u_index = graph.u_nodes.len();
-                graph.u_key2index.insert(u.clone(), u_index);
-                graph.u_nodes.push(u.clone());
-                graph.u_adjacency_list.push(vec![]);
-            } else {
-                u_index = graph.u_key2index[u];
+            weights.push(w);
+            if *position == kept.len() {
+                kept.push(((u, v), w));
+            } else if w > kept[*position].1 {
+                kept[*position].1 = w;
             }
+        }
+        index.nodes_edges.reserve(kept.len());
+        index.nodes_edges_use_index.reserve(kept.len());
+        for ((u, v), w) in kept {
+            index.push_edge(u, v, w);
+        }
+        Ok(graph)
+    }
 
-            graph.nodes_edges.push(edge.clone());
-            graph.nodes_edges_use_index.push(((u_index, v_index), w));
+    /// The index of the offline node with this key.
+    pub fn u_index(&self, key: &Key) -> Result<usize, Error<Key>> {
+        key_index(&self.u_key2index, Side::Offline, key)
+    }
 
-            graph.v_adjacency_list[v_index].push((u_index, w));
-            graph.u_adjacency_list[u_index].push((v_index, w));
-        }
-        graph
+    /// The index of the online node with this key, i.e. its arrival order.
+    pub fn v_index(&self, key: &Key) -> Result<usize, Error<Key>> {
+        key_index(&self.v_key2index, Side::Online, key)
     }
 
-    pub fn insert_u(self: &mut Self, key: Key) -> Result<(), String> {
-        if self.u_nodes.contains(&key) {
-            Err("The u nodes already have this key".to_owned())
-        } else {
-            let u_index = self.u_nodes.len();
-            self.u_nodes.push(key);
-            self.u_adjacency_list.push(vec![]);
-            self.u_key2index.insert(key, u_index);
-            Ok(())
+    /// Adds the edge between `u` and `v`, the nodes which aren't in the
+    /// graph yet are added as in `from_edges`, so a new online node
+    /// arrives after all the others. Like in `from_edges`, only the same
+    /// pair with the same weight is a duplicate, and an edge already
+    /// between `u` and `v` takes the heavier of the two weights.
+    pub fn insert_edge(&mut self, u: Key, v: Key, weight: Weight) -> Result<(), Error<Key>> {
+        let mut index = self.index();
+        match index.edge_data(&u, &v) {
+            Some(old) if old == weight => Err(Error::DuplicateEdge {
+                offline: u,
+                online: v,
+            }),
+            Some(old) => {
+                if weight > old {
+                    index.set_edge_data(&u, &v, weight)?;
+                }
+                Ok(())
+            }
+            None => {
+                index.push_edge(u, v, weight);
+                Ok(())
+            }
         }
     }
 
-    pub fn insert_v(self: &mut Self, key: Key) -> Result<(), String> {
-        if self.v_nodes.contains(&key) {
-            Err("The v nodes already have this key".to_owned())
-        } else {
-            let v_index = self.v_nodes.len();
-            self.v_nodes.push(key);
-            self.v_adjacency_list.push(vec![]);
-            self.v_key2index.insert(key, v_index);
-            Ok(())
+    /// Removes the edge between `u` and `v`, both nodes stay in the graph.
+    pub fn remove_edge(&mut self, u: &Key, v: &Key) -> Result<(), Error<Key>> {
+        self.index().remove_edge(u, v)
+    }
+
+    /// Removes the offline node `u` with all its edges,
+    /// the offline nodes after it move one index down.
+    pub fn remove_u(&mut self, u: &Key) -> Result<(), Error<Key>> {
+        self.index().remove_u(u)
+    }
+
+    /// Removes the online node `v` with all its edges,
+    /// the online nodes arriving after it move one index down.
+    pub fn remove_v(&mut self, v: &Key) -> Result<(), Error<Key>> {
+        self.index().remove_v(v)
+    }
+
+    pub fn insert_u(&mut self, key: Key) -> Result<(), Error<Key>> {
+        self.index().insert_u(key)
+    }
+
+    pub fn insert_v(&mut self, key: Key) -> Result<(), Error<Key>> {
+        self.index().insert_v(key)
+    }
+
+    fn index(&mut self) -> WeightedIndex<'_, Key, Weight> {
+        GraphIndex {
+            v_nodes: &mut self.v_nodes,
+            u_nodes: &mut self.u_nodes,
+            nodes_edges: &mut self.nodes_edges,
+            nodes_edges_use_index: &mut self.nodes_edges_use_index,
+            v_key2index: &mut self.v_key2index,
+            u_key2index: &mut self.u_key2index,
+            v_adjacency_list: &mut self.v_adjacency_list,
+            u_adjacency_list: &mut self.u_adjacency_list,
         }
     }
 }
@@ -3,6 +3,7 @@ mod tests {
     use std::vec;
 
     use onlinematching::bigraph::Bigraph;
+    use onlinematching::papers::estimator::Estimator;
 
     #[test]
     fn bigraph_random_alg_test() {
@@ -46,15 +47,398 @@ mod tests {
         let graph = Bigraph::from_edges(&edges);
         let duration = 2;
         let graph = graph.into_reuseable_online(duration);
-        let mut opt = 0.;
-        let mut alg = 0.;
-        for _ in 0..10000 {
-            opt += graph.OPT();
-            alg += graph
-                .ALG::<onlinematching::papers::reuseableresource::identical::algorithm::Ranking>();
+        let estimation = Estimator::new(10000, 0)
+            .estimate::<onlinematching::papers::reuseableresource::identical::algorithm::Ranking, _>(
+                &graph,
+            )
+            .unwrap();
+        println!("the ratio is {:?}", estimation.ratio);
+        println!("alg: {:?}, opt: {:?}", estimation.alg, estimation.opt);
+        assert!(estimation.ratio.mean > 0. && estimation.ratio.upper <= 1. + 1e-9);
+    }
+
+    #[test]
+    fn bigraph_opt_test() {
+        let graph = onlinematching::papers::kvv90::example::random_worst_case(50);
+        assert_eq!(graph.OPT(), 100.);
+        let graph = onlinematching::papers::kvv90::example::ranking_worst_case(50);
+        assert_eq!(graph.OPT(), 50.);
+
+        // u1 and u2 can only be matched through v1
+        let edges = vec![(1, 1), (2, 1), (3, 2), (3, 3), (4, 3)];
+        let (size, matching) = Bigraph::from_edges(&edges).maximum_matching();
+        let graph = Bigraph::from_edges(&edges).into_online();
+        assert_eq!(size, 3);
+        assert_eq!(graph.OPT(), 3.);
+        let matched: Vec<usize> = matching.iter().filter_map(|&u| u).collect();
+        assert_eq!(matched.len(), size);
+        for (i, &u) in matched.iter().enumerate() {
+            assert!(!matched[i + 1..].contains(&u));
         }
-        let ratio = alg / opt;
-        println!("the ratio is {:?}", ratio);
-        println!("alg: {:?}, opt: {:?}", alg, opt);
+    }
+
+    #[test]
+    fn exact_ranking_test() {
+        use onlinematching::papers::kvv90::{example::ranking_worst_case, Ranking};
+        // the i-th offline node of the upper triangular graph is matched
+        // with probability sum_{k=1}^{i} (-1)^(k+1) / k!, which tends to 1 - 1/e
+        let mut expected = 0.;
+        let mut matched = 0.;
+        let mut term = -1.;
+        for n in 1..=7 {
+            term = -term / n as f64;
+            matched += term;
+            expected += matched;
+            let exact = ranking_worst_case(n).exact_ranking_ALG();
+            assert!((exact - expected).abs() < 1e-9, "n = {}", n);
+        }
+
+        // a fixed rank permutation replays one run
+        let graph = ranking_worst_case(3);
+        let mut ranking = Ranking::with_ranks(vec![2, 1, 0]);
+        let alg: Vec<Option<usize>> = graph.iter().map(|adj| ranking.dispatch(adj)).collect();
+        assert_eq!(alg, vec![Some(2), Some(1), None]);
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 10 offline nodes")]
+    fn exact_ranking_guard_test() {
+        onlinematching::papers::kvv90::example::ranking_worst_case(11).exact_ranking_ALG();
+    }
+
+    #[test]
+    fn reuseableresource_opt_test() {
+        // u1 serves v1 and v3 only if it is back after one arrival
+        let edges = vec![(1, 1), (1, 2), (1, 3), (2, 2)];
+        let graph = Bigraph::from_edges(&edges).into_reuseable_online(2);
+        assert_eq!(graph.OPT(), 3.);
+        let mut graph = Bigraph::from_edges(&edges).into_reuseable_online(3);
+        assert_eq!(graph.OPT(), 2.);
+        assert!(graph.fractional_OPT() >= 2.);
+        graph.set_opt(2.5);
+        assert_eq!(graph.OPT(), 2.5);
+
+        let graph =
+            onlinematching::papers::reuseableresource::identical::example::z_graph_with_duration(
+                6, 1,
+            );
+        assert_eq!(graph.OPT(), 6.);
+
+        // OPT is always the integral optimum, whatever the size
+        let graph =
+            onlinematching::papers::reuseableresource::identical::example::z_graph_with_duration(
+                10, 3,
+            );
+        assert_eq!(graph.OPT(), graph.integral_OPT());
+        assert!(graph.OPT() <= graph.fractional_OPT() + 1e-9);
+    }
+
+    #[test]
+    fn seeded_alg_test() {
+        use rand::{rngs::StdRng, SeedableRng};
+        type Random = onlinematching::papers::kvv90::Random;
+        type Ranking = onlinematching::papers::kvv90::Ranking;
+        let graph = onlinematching::papers::kvv90::example::random_worst_case(100);
+        for seed in 0..10 {
+            let alg1 = graph.ALG_with_rng::<Random>(&mut StdRng::seed_from_u64(seed));
+            let alg2 = graph.ALG_with_rng::<Random>(&mut StdRng::seed_from_u64(seed));
+            assert_eq!(alg1, alg2);
+            let alg1 = graph.ALG_with_rng::<Ranking>(&mut StdRng::seed_from_u64(seed));
+            let alg2 = graph.ALG_with_rng::<Ranking>(&mut StdRng::seed_from_u64(seed));
+            assert_eq!(alg1, alg2);
+        }
+    }
+
+    #[test]
+    fn run_outcome_test() {
+        use rand::{rngs::StdRng, SeedableRng};
+        type Ranking = onlinematching::papers::kvv90::Ranking;
+        let edges = vec![("u1", "v1"), ("u2", "v1"), ("u2", "v2"), ("u3", "v3")];
+        let graph = Bigraph::from_edges(&edges).into_online();
+        let outcome = graph.run::<Ranking>(&mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(outcome.decisions.len(), 3);
+        assert_eq!(outcome.decisions[2].offline, Some("u3"));
+        let matched = outcome.matched_edges();
+        assert_eq!(matched.len() as f64, outcome.objective);
+        for (u, v) in matched {
+            assert!(edges.contains(&(u, v)));
+        }
+        let load: f64 = outcome.offline.iter().map(|state| state.load).sum();
+        assert_eq!(load, outcome.objective);
+    }
+
+    // Always takes the first neighbour, whether it is still free or not.
+    struct FirstNeighbour;
+
+    use onlinematching::papers::algorithm::algorithm::OnlineAlgorithm;
+
+    impl<OfflineInfo> OnlineAlgorithm<usize, OfflineInfo> for FirstNeighbour {
+        fn init(_offline_info: OfflineInfo) -> Self {
+            FirstNeighbour
+        }
+
+        fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
+            online_adjacent.first().copied()
+        }
+
+        fn alg_output(self) -> f64 {
+            0.
+        }
+    }
+
+    #[test]
+    fn violation_test() {
+        use onlinematching::papers::validation::Violation;
+        use rand::{rngs::StdRng, SeedableRng};
+        let edges = vec![(1, 1), (2, 1), (1, 2), (2, 3)];
+        let graph = Bigraph::from_edges(&edges).into_online();
+        let violation = graph
+            .run::<FirstNeighbour>(&mut StdRng::seed_from_u64(0))
+            .unwrap_err();
+        assert_eq!(
+            violation,
+            Violation::AlreadyMatched {
+                online: 1,
+                offline: 0
+            }
+        );
+
+        // with duration 2 the first offline node is back for the third arrival
+        let graph = Bigraph::from_edges(&edges).into_reuseable_online(2);
+        let violation = graph
+            .run::<FirstNeighbour>(&mut StdRng::seed_from_u64(0))
+            .unwrap_err();
+        assert_eq!(
+            violation,
+            Violation::Busy {
+                online: 1,
+                offline: 0,
+                available_at: 2
+            }
+        );
+
+        // in random order the violation names the online node, not the
+        // time it arrived at; v1 is matched in conflict when v2 came first
+        let graph = Bigraph::from_edges(&edges).into_online();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut v1_second = false;
+        for _ in 0..20 {
+            match graph
+                .random_order_run::<FirstNeighbour>(&mut rng)
+                .unwrap_err()
+            {
+                Violation::AlreadyMatched { online, offline } => {
+                    assert!(online < 2 && offline == 0);
+                    v1_second |= online == 0;
+                }
+                violation => panic!("unexpected {}", violation),
+            }
+        }
+        assert!(v1_second);
+    }
+
+    // Always takes the first advertiser, whether it has budget left or not.
+    struct FirstBidder;
+
+    impl<OfflineInfo> OnlineAlgorithm<(usize, f64), OfflineInfo> for FirstBidder {
+        fn init(_offline_info: OfflineInfo) -> Self {
+            FirstBidder
+        }
+
+        fn dispatch(&mut self, online_adjacent: &Vec<(usize, f64)>) -> Option<usize> {
+            online_adjacent.first().map(|&(u, _)| u)
+        }
+
+        fn alg_output(self) -> f64 {
+            0.
+        }
+    }
+
+    #[test]
+    fn budget_exhausted_test() {
+        use onlinematching::papers::validation::Violation;
+        use onlinematching::weightedbigraph::WBigraph;
+        use rand::{rngs::StdRng, SeedableRng};
+        // the second bid spends exactly the budget, the third one has
+        // nothing left to spend
+        let edges = vec![((1, 1), 1.), ((1, 2), 1.), ((1, 3), 1.)];
+        let g = WBigraph::from_edges(&edges).into_adwords(vec![2.]);
+        assert_eq!(
+            g.run::<FirstBidder>(&mut StdRng::seed_from_u64(0))
+                .unwrap_err(),
+            Violation::BudgetExhausted {
+                online: 2,
+                offline: 0
+            }
+        );
+        let g = WBigraph::from_edges(&edges).into_adwords(vec![3.]);
+        let outcome = g.run::<FirstBidder>(&mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(outcome.offline[0].load, 3.);
+    }
+
+    #[test]
+    fn stream_test() {
+        use onlinematching::papers::algorithm::stream_ALG;
+        use rand::{rngs::StdRng, SeedableRng};
+        use std::sync::mpsc;
+        type Ranking = onlinematching::papers::kvv90::Ranking;
+        let n = 100;
+        let graph = onlinematching::papers::kvv90::example::ranking_worst_case(n);
+        // the arrivals of ranking_worst_case generated on the fly
+        let arrivals = (0..n).map(|v| (v..n).collect::<Vec<_>>());
+        for seed in 0..5 {
+            let alg = graph.ALG_with_rng::<Ranking>(&mut StdRng::seed_from_u64(seed));
+            let streamed =
+                stream_ALG::<Ranking, _>(n, arrivals.clone(), &mut StdRng::seed_from_u64(seed));
+            assert_eq!(streamed, Ok(alg));
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let producer = std::thread::spawn(move || {
+            for v in 0..n {
+                sender.send((v..n).collect::<Vec<_>>()).unwrap();
+            }
+        });
+        let streamed = stream_ALG::<Ranking, _>(n, receiver, &mut StdRng::seed_from_u64(0));
+        producer.join().unwrap();
+        let alg = graph.ALG_with_rng::<Ranking>(&mut StdRng::seed_from_u64(0));
+        assert_eq!(streamed, Ok(alg));
+
+        // a stream isn't validated any less than a graph
+        let arrivals = vec![vec![0], vec![0]];
+        let violation =
+            stream_ALG::<FirstNeighbour, _>(1, &arrivals, &mut StdRng::seed_from_u64(0))
+                .unwrap_err();
+        assert_eq!(
+            violation,
+            onlinematching::papers::validation::Violation::AlreadyMatched {
+                online: 1,
+                offline: 0
+            }
+        );
+    }
+
+    #[test]
+    fn instance_test() {
+        use onlinematching::papers::instance::{mean_ratio, Instance, Simulate};
+        use onlinematching::papers::{adwords, kvv90, reuseableresource, stochastic_reward};
+        use rand::{rngs::StdRng, SeedableRng};
+
+        // generic tooling, written once for every model
+        fn check<Alg, Inst: Simulate<Alg>>(instance: &Inst) -> f64 {
+            let arrivals = instance.arrivals().count();
+            assert_eq!(arrivals, instance.online_keys().len());
+            let outcome = instance.simulate(&mut StdRng::seed_from_u64(0)).unwrap();
+            assert_eq!(outcome.decisions.len(), instance.online_size());
+            assert_eq!(outcome.offline.len(), instance.offline_keys().len());
+            assert!(outcome.objective <= instance.OPT() + 1e-9);
+            let ratio = mean_ratio(instance, 20, &mut StdRng::seed_from_u64(0)).unwrap();
+            assert!(ratio > 0. && ratio <= 1. + 1e-9);
+            ratio
+        }
+
+        let graph = kvv90::example::ranking_worst_case(10);
+        assert_eq!(graph.offline_info(), 10);
+        check::<kvv90::Ranking, _>(&graph);
+
+        let graph = reuseableresource::identical::example::z_graph_with_duration(6, 2);
+        assert_eq!(graph.offline_info(), (6, 2));
+        check::<reuseableresource::identical::algorithm::Ranking, _>(&graph);
+
+        let graph = adwords::greedy::example::greedy_worst_case(3);
+        let ratio = check::<adwords::greedy::Greddy<f64>, _>(&graph);
+        // greedy takes the bids of 1 first, OPT also earns the bids of 0.99
+        assert!((ratio - 3. / (3. + 3. * 0.99)).abs() < 1e-9);
+
+        let graph = stochastic_reward::mp12::example::gk(2, 5);
+        check::<stochastic_reward::mp12::Balance, _>(&graph);
+
+        // 30 online nodes sampled from the 2 types
+        let graph =
+            Bigraph::from_edges(&vec![(0, 0), (1, 0), (1, 1)]).into_known_iid(vec![1., 1.], 30);
+        assert_eq!(graph.online_size(), 30);
+        check::<kvv90::Ranking, _>(&graph);
+
+        // nothing to match, 0 / 0 counts as optimal
+        let mut graph = Bigraph::new();
+        graph.insert_u("u1").unwrap();
+        graph.insert_v("v1").unwrap();
+        let graph = graph.into_online();
+        assert_eq!(graph.OPT(), 0.);
+        let ratio = mean_ratio::<kvv90::Ranking, _>(&graph, 5, &mut StdRng::seed_from_u64(0));
+        assert_eq!(ratio.unwrap(), 1.);
+        let estimation = Estimator::new(5, 0)
+            .estimate::<kvv90::Ranking, _>(&graph)
+            .unwrap();
+        assert_eq!(estimation.ratio.mean, 1.);
+    }
+
+    #[test]
+    fn random_order_test() {
+        use rand::{rngs::StdRng, SeedableRng};
+        type Ranking = onlinematching::papers::kvv90::Ranking;
+        let graph =
+            Bigraph::from_edges(&vec![("u1", "v1"), ("u2", "v1"), ("u1", "v2")]).into_online();
+        // when v1 arrives first it takes u1 with probability 1/2 and v2 is
+        // left unmatched, when v2 arrives first both are matched
+        let alg = graph.random_order_ALG::<Ranking>(20000, &mut StdRng::seed_from_u64(0));
+        assert!((alg - 1.75).abs() < 0.02, "alg = {}", alg);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut v2_first = false;
+        for _ in 0..20 {
+            let outcome = graph.random_order_run::<Ranking>(&mut rng).unwrap();
+            let mut arrived: Vec<_> = outcome.decisions.iter().map(|d| d.online).collect();
+            if arrived[0] == "v2" {
+                v2_first = true;
+                assert_eq!(outcome.objective, 2.);
+            }
+            arrived.sort();
+            assert_eq!(arrived, vec!["v1", "v2"]);
+        }
+        assert!(v2_first);
+
+        let a = graph.random_order_ALG::<Ranking>(10, &mut StdRng::seed_from_u64(7));
+        let b = graph.random_order_ALG::<Ranking>(10, &mut StdRng::seed_from_u64(7));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn estimator_test() {
+        use onlinematching::papers::kvv90;
+        let graph = kvv90::example::ranking_worst_case(20);
+        let estimator = |threads| Estimator {
+            threads,
+            batch: 300,
+            ..Estimator::new(2000, 7)
+        };
+        // the same seed gives the same estimate on any number of threads
+        let single = estimator(1).estimate::<kvv90::Ranking, _>(&graph).unwrap();
+        let parallel = estimator(4).estimate::<kvv90::Ranking, _>(&graph).unwrap();
+        assert_eq!(single, parallel);
+        assert_eq!(single.trials, 2000);
+        assert_eq!(single.opt, 20.);
+        let alg = single.alg;
+        assert!((alg.standard_error - (alg.variance / 2000.).sqrt()).abs() < 1e-12);
+        assert!((alg.half_width() - 1.959964 * alg.standard_error).abs() < 1e-6);
+        assert!((single.ratio.mean - alg.mean / 20.).abs() < 1e-12);
+        // Ranking is at least 1 - 1/e competitive in expectation
+        assert!(single.ratio.upper > 1. - 1. / std::f64::consts::E);
+
+        // the target is on the ratio, whose interval is 20 times narrower
+        let stopped = Estimator {
+            target_half_width: Some(0.0025),
+            ..estimator(3)
+        }
+        .estimate::<kvv90::Ranking, _>(&graph)
+        .unwrap();
+        assert!(stopped.trials < 2000 && stopped.trials % 300 == 0);
+        assert!(stopped.ratio.half_width() <= 0.0025);
+        assert!(stopped.alg.half_width() > 0.0025);
+
+        // the violation of the first trial is reported
+        let edges = vec![(1, 1), (2, 1), (1, 2), (2, 3)];
+        let graph = Bigraph::from_edges(&edges).into_online();
+        assert!(estimator(4).estimate::<FirstNeighbour, _>(&graph).is_err());
     }
 }
@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod test_b_matching {
+    use onlinematching::bigraph::Bigraph;
+    use onlinematching::papers::b_matching::{
+        graph::example, kp00::Balance, water_filling::WaterFilling,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn b_matching_opt_test() {
+        for b in [1, 3] {
+            assert_eq!(example::upper_triangular(10, b).OPT(), (10 * b) as f64);
+        }
+        // u1 can take two of the three online nodes, u2 only v3
+        let edges = vec![(1, 1), (1, 2), (1, 3), (2, 3)];
+        let instance = Bigraph::from_edges(&edges).into_b_matching(vec![2, 1]);
+        assert_eq!(instance.OPT(), 3.);
+    }
+
+    #[test]
+    fn balance_test() {
+        let e = 1. - 1. / std::f64::consts::E;
+        let n = 20;
+        // with b = 1 the ties make BALANCE a greedy with the worst choices
+        let instance = example::upper_triangular(n, 1);
+        assert_eq!(instance.ALG::<Balance>() / instance.OPT(), 0.5);
+        let mut previous = 0.;
+        for b in [1, 2, 5, 20] {
+            let instance = example::upper_triangular(n, b);
+            let ratio = instance.ALG::<Balance>() / instance.OPT();
+            assert!(ratio >= previous && ratio < e + 0.02, "b = {}", b);
+            assert!(ratio >= 1. - 1. / (1. + 1. / b as f64).powi(b as i32) - 1e-9);
+            previous = ratio;
+        }
+        assert!(previous > e - 0.01);
+    }
+
+    #[test]
+    fn water_filling_test() {
+        let n = 20;
+        // every type is spread evenly over its neighbours, so on the blown up
+        // graph the ratio doesn't depend on b
+        let ratio = |b| {
+            let instance = example::upper_triangular(n, b);
+            instance.ALG::<WaterFilling>() / instance.OPT()
+        };
+        let one = ratio(1);
+        assert!((one - ratio(4)).abs() < 1e-9);
+        assert!(one >= 1. - 1. / std::f64::consts::E);
+        assert!(one < 0.7);
+
+        let outcome = example::upper_triangular(3, 1)
+            .run::<WaterFilling>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        // the first online node is split evenly over the three offline nodes
+        assert_eq!(outcome.allocations[0].len(), 3);
+        for &(_, fraction) in outcome.allocations[0].iter() {
+            assert!((fraction - 1. / 3.).abs() < 1e-9);
+        }
+        let load: f64 = outcome.offline.iter().map(|state| state.load).sum();
+        assert!((load - outcome.objective).abs() < 1e-9);
+        assert!(outcome.offline.iter().all(|state| state.load <= 1. + 1e-9));
+    }
+
+    #[test]
+    fn b_matching_violation_test() {
+        use onlinematching::papers::b_matching::graph::algorithm::FractionalAlgorithm;
+        use onlinematching::papers::validation::Violation;
+        // Puts the whole online node on every neighbour.
+        struct Everywhere;
+
+        impl FractionalAlgorithm<usize, Vec<usize>> for Everywhere {
+            fn init(_capacities: Vec<usize>) -> Self {
+                Everywhere
+            }
+
+            fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Vec<(usize, f64)> {
+                online_adjacent.iter().map(|&u| (u, 1.)).collect()
+            }
+
+            fn alg_output(self) -> f64 {
+                0.
+            }
+        }
+
+        let edges = vec![(1, 1), (2, 1)];
+        let instance = Bigraph::from_edges(&edges).into_b_matching(vec![1, 1]);
+        assert_eq!(
+            instance
+                .run::<Everywhere>(&mut StdRng::seed_from_u64(0))
+                .unwrap_err(),
+            Violation::InvalidFraction {
+                online: 0,
+                total: 2.
+            }
+        );
+        let edges = vec![(1, 1), (1, 2)];
+        let instance = Bigraph::from_edges(&edges).into_b_matching(vec![1]);
+        assert_eq!(
+            instance
+                .run::<Everywhere>(&mut StdRng::seed_from_u64(0))
+                .unwrap_err(),
+            Violation::CapacityExceeded {
+                online: 1,
+                offline: 0
+            }
+        );
+        assert!(Bigraph::from_edges(&edges)
+            .try_into_b_matching(vec![1, 1])
+            .is_err());
+    }
+
+    #[test]
+    fn b_matching_estimator_test() {
+        use onlinematching::papers::estimator::Estimator;
+        use onlinematching::papers::instance::mean_fractional_ratio;
+        let instance = example::upper_triangular(10, 2);
+        let balance = Estimator::new(3, 0)
+            .estimate_fractional::<Balance, _>(&instance)
+            .unwrap();
+        assert_eq!(balance.opt, 20.);
+        // both are deterministic
+        assert_eq!(balance.alg.variance, 0.);
+        assert_eq!(balance.ratio.mean, instance.ALG::<Balance>() / 20.);
+        let water_filling = Estimator::new(3, 0)
+            .estimate_fractional::<WaterFilling, _>(&instance)
+            .unwrap();
+        let ratio =
+            mean_fractional_ratio::<WaterFilling, _>(&instance, 3, &mut StdRng::seed_from_u64(0))
+                .unwrap();
+        assert!((water_filling.ratio.mean - ratio).abs() < 1e-12);
+        assert!(ratio > balance.ratio.mean);
+    }
+}
@@ -17,6 +17,25 @@ mod tests_bigraph {
         drop(g);
     }
 
+    #[test]
+    fn maximum_matching_test() {
+        let g = Bigraph::from_edges(&vec![
+            ("u1", "v1"),
+            ("u2", "v1"),
+            ("u1", "v2"),
+            ("u3", "v3"),
+            ("u3", "v4"),
+        ]);
+        let (size, matching) = g.maximum_matching();
+        assert_eq!(size, 3);
+        assert_eq!(matching.len(), 4);
+        for (v, u) in matching.iter().enumerate() {
+            if let Some(u) = u {
+                assert!(g.v_adjacency_list[v].contains(u));
+            }
+        }
+    }
+
     #[test]
     fn weighted_bigraph_test() {
         let mut g = WBigraph::from_edges(&vec![
@@ -37,7 +56,9 @@ mod tests_bigraph {
     fn weighted_greddy_test() {
         let n = 5;
         let g = onlinematching::papers::adwords::greedy::example::greedy_worst_case(n);
-        let opt = (0.99 + 1.) * n as f64;
+        let opt = g.OPT();
+        assert!((opt - (0.99 + 1.) * n as f64).abs() < 1e-6);
+        assert!((g.fractional_OPT() - opt).abs() < 1e-6);
         let alg = g.ALG::<onlinematching::papers::adwords::greedy::Greddy<f64>>();
         println!("{:?}", g);
         println!("opt = {:?}, alg = {:?}", opt, alg);
@@ -48,7 +69,8 @@ mod tests_bigraph {
         let n = 3;
         let m = 4;
         let g = onlinematching::papers::adwords::msvv05::example::thick_triangle_case(m, n);
-        let opt = n as f64 * m as f64;
+        let opt = g.OPT();
+        assert!((opt - n as f64 * m as f64).abs() < 1e-6);
         // let alg = g.ALG::<onlinematching::papers::adwords::greedy::Greddy<i32>>();
         let alg = g.ALG::<onlinematching::papers::adwords::msvv05::MSVV<i32>>();
         println!("----------------------");
@@ -56,4 +78,234 @@ mod tests_bigraph {
         println!("----------------------");
         println!("opt = {:?}, alg = {:?}", opt, alg);
     }
+
+    #[test]
+    fn adwords_opt_test() {
+        // a single online node can't be split, but the LP can split it
+        let g = WBigraph::from_edges(&vec![((0, 0), 2.), ((1, 0), 2.), ((0, 1), 1.)]);
+        let g = g.into_adwords(vec![1.5, 1.5]);
+        let (opt, assignment) = g.opt_allocation();
+        assert!((opt - 2.5).abs() < 1e-6);
+        assert_eq!(assignment.len(), 2);
+        assert!(assignment[0].is_some());
+        assert!((g.fractional_OPT() - 3.).abs() < 1e-6);
+        assert_eq!(g.OPT(), g.integral_OPT());
+
+        // OPT is always the integral optimum, whatever the size
+        let n = 20;
+        let g = onlinematching::papers::adwords::greedy::example::greedy_worst_case(n);
+        assert_eq!(g.OPT(), g.integral_OPT());
+        assert!(g.OPT() <= g.fractional_OPT() + 1e-6);
+    }
+
+    #[test]
+    fn adwords_run_test() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let n = 3;
+        let g = onlinematching::papers::adwords::greedy::example::greedy_worst_case(n);
+        let outcome = g
+            .run::<onlinematching::papers::adwords::greedy::Greddy<f64>>(
+                &mut StdRng::seed_from_u64(0),
+            )
+            .unwrap();
+        assert_eq!(outcome.objective, n as f64);
+        for (i, decision) in outcome.decisions.iter().enumerate() {
+            assert_eq!(decision.online, i);
+            if i < n {
+                assert_eq!(decision.offline, Some(1));
+                assert_eq!(decision.weight, Some(1.));
+            } else {
+                assert_eq!(decision.offline, None);
+            }
+        }
+        assert_eq!(outcome.offline[0].load, 0.);
+        assert!(outcome.offline[0].available);
+        assert_eq!(outcome.offline[1].load, n as f64);
+        assert!(!outcome.offline[1].available);
+    }
+
+    #[test]
+    #[should_panic(expected = "same edge")]
+    fn duplicate_edge_test() {
+        Bigraph::from_edges(&vec![("u1", "v1"), ("u2", "v1"), ("u1", "v1")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "same edge")]
+    fn weighted_duplicate_edge_test() {
+        // of the same pair of nodes with other weights the heaviest is kept
+        let g = WBigraph::from_edges(&vec![
+            (("u1", "v1"), 1),
+            (("u2", "v1"), 3),
+            (("u1", "v1"), 2),
+            (("u1", "v1"), 0),
+        ]);
+        assert_eq!(g.nodes_edges, vec![(("u1", "v1"), 2), (("u2", "v1"), 3)]);
+        assert_eq!(g.v_adjacency_list, vec![vec![(0, 2), (1, 3)]]);
+        assert_eq!(g.u_adjacency_list, vec![vec![(0, 2)], vec![(0, 3)]]);
+        WBigraph::from_edges(&vec![(("u1", "v1"), 1), (("u1", "v1"), 1)]);
+    }
+
+    #[test]
+    fn large_bigraph_test() {
+        let n = 300;
+        let g = onlinematching::papers::kvv90::example::random_worst_case(n);
+        assert_eq!(g.OPT(), (2 * n) as f64);
+    }
+
+    #[test]
+    fn try_constructors_test() {
+        use onlinematching::error::{Error, Side};
+        let err = Bigraph::try_from_edges(&vec![("u1", "v1"), ("u1", "v1")]).unwrap_err();
+        assert_eq!(
+            err,
+            Error::DuplicateEdge {
+                offline: "u1",
+                online: "v1"
+            }
+        );
+
+        let mut g = Bigraph::try_from_edges(&vec![("u1", "v1"), ("u2", "v1")]).unwrap();
+        assert_eq!(
+            g.insert_u("u2"),
+            Err(Error::DuplicateNode {
+                side: Side::Offline,
+                key: "u2"
+            })
+        );
+        assert_eq!(g.u_index(&"u2"), Ok(1));
+        assert_eq!(
+            g.v_index(&"v2"),
+            Err(Error::UnknownKey {
+                side: Side::Online,
+                key: "v2"
+            })
+        );
+
+        let g = WBigraph::try_from_edges(&vec![(("u1", "v1"), 1.), (("u2", "v1"), 2.)]).unwrap();
+        let err = g.try_into_adwords(vec![1.]).unwrap_err();
+        assert_eq!(
+            err,
+            Error::BudgetLengthMismatch {
+                offline_size: 2,
+                budgets: 1
+            }
+        );
+        assert_eq!(err.to_string(), "1 budgets are given for 2 offline nodes");
+    }
+
+    #[test]
+    fn mutation_test() {
+        use onlinematching::error::{Error, Side};
+        let edges = vec![
+            ("u1", "v1"),
+            ("u2", "v1"),
+            ("u1", "v2"),
+            ("u3", "v2"),
+            ("u2", "v3"),
+        ];
+        let mut g = Bigraph::new();
+        for &(u, v) in edges.iter() {
+            g.insert_edge(u, v).unwrap();
+        }
+        assert_eq!(g, Bigraph::from_edges(&edges));
+        assert_eq!(
+            g.insert_edge("u1", "v2"),
+            Err(Error::DuplicateEdge {
+                offline: "u1",
+                online: "v2"
+            })
+        );
+
+        g.remove_edge(&"u2", &"v3").unwrap();
+        assert_eq!(
+            g.remove_edge(&"u2", &"v3"),
+            Err(Error::UnknownEdge {
+                offline: "u2",
+                online: "v3"
+            })
+        );
+        g.insert_edge("u2", "v3").unwrap();
+        assert_eq!(g, Bigraph::from_edges(&edges));
+
+        let mut without_u1 = Bigraph::from_edges(&edges);
+        without_u1.remove_u(&"u1").unwrap();
+        assert_eq!(
+            without_u1,
+            Bigraph::from_edges(&vec![("u2", "v1"), ("u3", "v2"), ("u2", "v3")])
+        );
+        assert_eq!(without_u1.v_adjacency_list, vec![vec![0], vec![1], vec![0]]);
+
+        let mut without_v2 = Bigraph::from_edges(&edges);
+        without_v2.remove_v(&"v2").unwrap();
+        let mut expected = Bigraph::from_edges(&vec![("u1", "v1"), ("u2", "v1"), ("u2", "v3")]);
+        expected.insert_u("u3").unwrap();
+        assert_eq!(without_v2, expected);
+        assert_eq!(
+            without_v2.remove_v(&"v2"),
+            Err(Error::UnknownKey {
+                side: Side::Online,
+                key: "v2"
+            })
+        );
+    }
+
+    #[test]
+    fn weighted_mutation_test() {
+        use onlinematching::error::Error;
+        let edges = vec![
+            (("u1", "v1"), 1.),
+            (("u2", "v1"), 2.),
+            (("u1", "v2"), 3.),
+            (("u2", "v2"), 4.),
+        ];
+        let mut g = WBigraph::new();
+        for &((u, v), w) in edges.iter() {
+            g.insert_edge(u, v, w).unwrap();
+        }
+        assert_eq!(g, WBigraph::from_edges(&edges));
+
+        g.remove_u(&"u1").unwrap();
+        g.remove_edge(&"u2", &"v1").unwrap();
+        let mut expected = WBigraph::from_edges(&vec![(("u2", "v2"), 4.)]);
+        // v1 keeps its place in the arrival order
+        expected.remove_v(&"v2").unwrap();
+        expected.insert_v("v1").unwrap();
+        expected.insert_edge("u2", "v2", 4.).unwrap();
+        assert_eq!(g, expected);
+        assert_eq!(g.v_adjacency_list, vec![vec![], vec![(0, 4.)]]);
+        assert_eq!(g.u_adjacency_list, vec![vec![(1, 4.)]]);
+
+        // the same pair takes the heavier weight
+        g.insert_edge("u2", "v2", 5.).unwrap();
+        g.insert_edge("u2", "v2", 3.).unwrap();
+        assert_eq!(g.nodes_edges, vec![(("u2", "v2"), 5.)]);
+        assert_eq!(g.v_adjacency_list, vec![vec![], vec![(0, 5.)]]);
+        assert_eq!(g.u_adjacency_list, vec![vec![(1, 5.)]]);
+        assert_eq!(
+            g.insert_edge("u2", "v2", 5.),
+            Err(Error::DuplicateEdge {
+                offline: "u2",
+                online: "v2"
+            })
+        );
+        g.remove_edge(&"u2", &"v2").unwrap();
+        assert!(g.nodes_edges.is_empty());
+    }
+
+    #[test]
+    fn adwords_random_order_test() {
+        use rand::{rngs::StdRng, SeedableRng};
+        type Greedy = onlinematching::papers::adwords::greedy::Greddy<f64>;
+        let n = 3;
+        let g = onlinematching::papers::adwords::greedy::example::greedy_worst_case(n);
+        // the adversarial order is the worst one for greedy
+        let alg = g.random_order_ALG::<Greedy>(200, &mut StdRng::seed_from_u64(0));
+        assert!(alg > g.ALG::<Greedy>() && alg <= g.OPT());
+        let outcome = g
+            .random_order_run::<Greedy>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(outcome.decisions.len(), 2 * n);
+        assert!(outcome.objective <= g.OPT());
+    }
 }
@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod test_free_disposal {
+    use onlinematching::papers::estimator::Estimator;
+    use onlinematching::papers::free_disposal::{
+        greedy::Greedy, ocs_primal_dual::OcsPrimalDualHeuristic,
+    };
+    use onlinematching::weightedbigraph::WBigraph;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // The best matching of the online nodes from `v` on.
+    fn brute_force(v: usize, online_adjacency_list: &Vec<Vec<(usize, f64)>>, used: u32) -> f64 {
+        if v == online_adjacency_list.len() {
+            return 0.;
+        }
+        let mut best = brute_force(v + 1, online_adjacency_list, used);
+        for &(u, weight) in online_adjacency_list[v].iter() {
+            if used & (1 << u) == 0 {
+                let matched = weight + brute_force(v + 1, online_adjacency_list, used | (1 << u));
+                best = f64::max(best, matched);
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn max_weight_matching_test() {
+        use onlinematching::offline::matching::{heaviest, max_weight_matching};
+        // of parallel edges only the heaviest counts
+        let parallel = vec![vec![(0, 1.), (0, 5.)]];
+        assert_eq!(max_weight_matching(1, &parallel), (5., vec![Some(0)]));
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..300 {
+            let offline_size = rng.gen_range(1..7);
+            let mut online_adjacency_list = vec![vec![]; rng.gen_range(1..7)];
+            for online_adj in online_adjacency_list.iter_mut() {
+                for u in 0..offline_size {
+                    if rng.gen_bool(0.5) {
+                        online_adj.push((u, rng.gen_range(0..10) as f64));
+                        if rng.gen_bool(0.2) {
+                            online_adj.push((u, rng.gen_range(0..10) as f64));
+                        }
+                    }
+                }
+            }
+            let (weight, matching) = max_weight_matching(offline_size, &online_adjacency_list);
+            assert_eq!(weight, brute_force(0, &online_adjacency_list, 0));
+            let mut used = vec![false; offline_size];
+            let mut matched = 0.;
+            for (v, u) in matching.iter().enumerate() {
+                if let &Some(u) = u {
+                    assert!(!used[u]);
+                    used[u] = true;
+                    matched += heaviest(&online_adjacency_list[v], u);
+                }
+            }
+            assert_eq!(matched, weight);
+        }
+    }
+
+    #[test]
+    fn free_disposal_greedy_test() {
+        // v2 brings a heavier edge to u1, which disposes of the edge of v1
+        let edges = vec![((1, 1), 1.), ((1, 2), 3.), ((2, 2), 1.5)];
+        let instance = WBigraph::from_edges(&edges).into_free_disposal();
+        assert_eq!(instance.OPT(), 3.);
+        let outcome = instance
+            .run::<Greedy>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(outcome.decisions[1].offline, Some(1));
+        assert_eq!(outcome.decisions[1].weight, Some(3.));
+        assert_eq!(outcome.offline[0].load, 3.);
+        assert!(outcome.offline[1].available);
+        assert_eq!(outcome.objective, 3.);
+
+        // a parallel edge counts with the heaviest weight in OPT and ALG
+        let edges = vec![((1, 1), 1.), ((1, 1), 5.)];
+        let instance = WBigraph::from_edges(&edges).into_free_disposal();
+        assert_eq!(instance.OPT(), 5.);
+        let outcome = instance
+            .run::<Greedy>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(outcome.decisions[0].weight, Some(5.));
+        assert_eq!(outcome.objective, 5.);
+    }
+
+    #[test]
+    fn ocs_test() {
+        use onlinematching::papers::free_disposal::ocs::{unselected, OnlineCorrelatedSelection};
+        let mut rng = StdRng::seed_from_u64(0);
+        let trials = 20000;
+        for k in 1..5 {
+            // element 0 in k rounds, each time with a new element
+            let mut never = 0;
+            for _ in 0..trials {
+                let mut ocs = OnlineCorrelatedSelection::new(k + 1);
+                let selected = (0..k).any(|round| ocs.select((0, round + 1), &mut rng) == 0);
+                if !selected {
+                    never += 1;
+                }
+            }
+            let never = never as f64 / trials as f64;
+            assert!(never < unselected(k as u32) + 0.01, "k = {}", k);
+        }
+        // 2^(-k) for independent selections
+        assert!(unselected(3) < 0.5f64.powi(3));
+
+        // element 0 alone in the second round, which uses up a sender of
+        // the first, so the first and the third round are independent
+        let trials = 100000;
+        let mut never = 0;
+        for _ in 0..trials {
+            let mut ocs = OnlineCorrelatedSelection::new(3);
+            let first = ocs.select((0, 1), &mut rng);
+            assert_eq!(ocs.select_alone(0), 0);
+            let third = ocs.select((0, 2), &mut rng);
+            if first != 0 && third != 0 {
+                never += 1;
+            }
+        }
+        let never = never as f64 / trials as f64;
+        assert!((never - 0.25).abs() < 0.005, "never = {}", never);
+    }
+
+    #[test]
+    fn ocs_primal_dual_test() {
+        // both offline nodes offer v1 the same weight, but u1 is the only
+        // neighbour of v2: greedy keeps 1, a randomized pair 1.5 in expectation
+        let edges = vec![((1, 1), 1.), ((2, 1), 1.), ((1, 2), 1.)];
+        let instance = WBigraph::from_edges(&edges).into_free_disposal();
+        let greedy = Estimator::new(1, 0)
+            .estimate::<Greedy, _>(&instance)
+            .unwrap();
+        assert_eq!(greedy.alg.mean, 1.);
+        let primal_dual = Estimator::new(4000, 0)
+            .estimate::<OcsPrimalDualHeuristic, _>(&instance)
+            .unwrap();
+        assert!(primal_dual.alg.lower < 1.5 && 1.5 < primal_dual.alg.upper);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut edges = vec![];
+        for u in 0..20 {
+            for v in 0..20 {
+                if rng.gen_bool(0.2) {
+                    edges.push(((u, v), rng.gen_range(1. ..10.)));
+                }
+            }
+        }
+        let instance = WBigraph::from_edges(&edges).into_free_disposal();
+        let primal_dual = Estimator::new(500, 2)
+            .estimate::<OcsPrimalDualHeuristic, _>(&instance)
+            .unwrap();
+        assert!(primal_dual.ratio.mean > 0.5 && primal_dual.ratio.upper <= 1.);
+    }
+
+    #[test]
+    fn free_disposal_error_test() {
+        use onlinematching::error::Error;
+        let edges = vec![((1, 1), 1.), ((1, 2), -1.)];
+        assert_eq!(
+            WBigraph::from_edges(&edges)
+                .try_into_free_disposal()
+                .unwrap_err(),
+            Error::InvalidEdgeWeight {
+                offline: 1,
+                online: 2,
+                weight: -1.
+            }
+        );
+    }
+}
@@ -0,0 +1,274 @@
+#[cfg(test)]
+mod test_heterogeneous_reusable {
+    use onlinematching::bigraph::Bigraph;
+    use onlinematching::papers::estimator::Estimator;
+    use onlinematching::papers::instance::Instance;
+    use onlinematching::papers::reuseableresource::giu20::{Balance, RBA};
+    use onlinematching::papers::reuseableresource::heterogeneous::{
+        algorithm::Ranking, example, Duration,
+    };
+    use onlinematching::papers::reuseableresource::identical;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn duration_test() {
+        let duration = Duration::uniform(1, 3);
+        assert_eq!(duration.survival(10), vec![1., 2. / 3., 1. / 3.]);
+        assert_eq!(duration.survival(2), vec![1., 2. / 3.]);
+        assert!((duration.mean() - 2.).abs() < 1e-9);
+        assert_eq!(Duration::Fixed(0).survival(10), vec![1.]);
+        assert_eq!(Duration::Fixed(0).mean(), 1.);
+        assert_eq!(Duration::Fixed(usize::MAX).survival(3), vec![1.; 3]);
+
+        // a unit which never comes back, without overflow
+        let z_graph = example::z_graph(vec![Duration::Fixed(usize::MAX); 4]);
+        let outcome = z_graph
+            .run::<Ranking>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        for state in outcome.offline.iter() {
+            assert_eq!(state.available, state.load == 0.);
+        }
+        assert!((z_graph.OPT() - 4.).abs() < 1e-9);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut counts = [0; 4];
+        for _ in 0..30000 {
+            counts[duration.sample(&mut rng)] += 1;
+        }
+        assert_eq!(counts[0], 0);
+        for &count in counts[1..].iter() {
+            assert!((count as f64 / 30000. - 1. / 3.).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn fixed_durations_test() {
+        // the same fixed duration everywhere is the identical model
+        for (n, d) in [(10, 2), (10, 4)] {
+            let identical = identical::example::z_graph_with_duration(n, d);
+            let heterogeneous = example::z_graph(vec![Duration::Fixed(d); n]);
+            for seed in 0..5 {
+                assert_eq!(
+                    heterogeneous.ALG_with_rng::<Ranking>(1, &mut StdRng::seed_from_u64(seed)),
+                    identical.ALG_with_rng::<identical::algorithm::Ranking>(
+                        &mut StdRng::seed_from_u64(seed)
+                    )
+                );
+            }
+        }
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut edges = vec![];
+        for v in 0..12 {
+            for u in 0..4 {
+                if rng.gen_bool(0.4) {
+                    edges.push((u, v));
+                }
+            }
+        }
+        let identical = Bigraph::from_edges(&edges).into_reuseable_online(3);
+        let offline_size = identical.offline_keys().len();
+        let heterogeneous = Bigraph::from_edges(&edges)
+            .into_heterogeneous_reuseable(vec![Duration::Fixed(3); offline_size]);
+        assert!((heterogeneous.OPT() - identical.fractional_OPT()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stochastic_durations_test() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let graph = example::random(6, 40, 0.3, 6, &mut rng);
+        let outcome = graph.run::<Ranking>(&mut rng).unwrap();
+        for decision in outcome.decisions.iter() {
+            if let Some(weight) = decision.weight {
+                assert!((1. ..=6.).contains(&weight));
+            }
+        }
+        let matches: f64 = outcome.offline.iter().map(|state| state.load).sum();
+        assert_eq!(matches, outcome.objective);
+
+        let estimation = Estimator::new(2000, 0)
+            .estimate::<Ranking, _>(&graph)
+            .unwrap();
+        assert!(estimation.ratio.mean > 0.5 && estimation.ratio.mean < 1.);
+        // longer durations can only make the benchmark smaller
+        let longer = example::z_graph(vec![Duration::uniform(1, 5); 6]);
+        let shorter = example::z_graph(vec![Duration::uniform(1, 2); 6]);
+        assert!(longer.OPT() <= shorter.OPT() + 1e-9);
+    }
+
+    #[test]
+    fn capacities_test() {
+        // a single unit is the z-graph
+        for d in [2, 4] {
+            let z_graph = example::z_graph(vec![Duration::Fixed(d); 6]);
+            let with_capacity = example::z_graph_with_capacity(6, d, 1);
+            assert!((z_graph.OPT() - with_capacity.OPT()).abs() < 1e-9);
+        }
+        // no unit is ever back, so OPT is the perfect b-matching
+        let graph = example::z_graph_with_capacity(4, 20, 3);
+        assert!((graph.OPT() - 12.).abs() < 1e-9);
+        let outcome = graph.run::<Balance>(&mut StdRng::seed_from_u64(0)).unwrap();
+        for (state, &capacity) in outcome.offline.iter().zip(graph.capacities().iter()) {
+            assert!(state.load <= capacity as f64);
+        }
+        // more units at a time only help
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut edges = vec![];
+        for v in 0..30 {
+            for u in 0..5 {
+                if rng.gen_bool(0.4) {
+                    edges.push((u, v));
+                }
+            }
+        }
+        let durations = vec![Duration::uniform(2, 6); 5];
+        let single = Bigraph::from_edges(&edges).into_heterogeneous_reuseable(durations.clone());
+        let double =
+            Bigraph::from_edges(&edges).into_reuseable_with_capacities(durations, vec![2; 5]);
+        assert!(double.OPT() > single.OPT());
+        assert!(
+            double.ALG_with_rng::<RBA>(500, &mut rng) > single.ALG_with_rng::<RBA>(500, &mut rng)
+        );
+    }
+
+    #[test]
+    fn identical_rba_balance_test() {
+        for (n, d) in [(10, 2), (10, 4)] {
+            let identical = identical::example::z_graph_with_duration(n, d);
+            let opt = identical.OPT();
+            let ranking = identical
+                .ALG_with_rng::<identical::algorithm::Ranking>(&mut StdRng::seed_from_u64(0));
+            let graph = identical::example::z_graph_with_duration(n, d).into_heterogeneous();
+            assert!((graph.OPT() - opt).abs() < 1e-9);
+            // an OPT given by set_opt carries over
+            let mut overridden = identical::example::z_graph_with_duration(n, d);
+            overridden.set_opt(opt + 1.);
+            assert_eq!(overridden.into_heterogeneous().OPT(), opt + 1.);
+            assert_eq!(
+                graph.ALG_with_rng::<Ranking>(1, &mut StdRng::seed_from_u64(0)),
+                ranking
+            );
+            let balance = Estimator::new(1, 0).estimate::<Balance, _>(&graph).unwrap();
+            let rba = Estimator::new(500, 0).estimate::<RBA, _>(&graph).unwrap();
+            // the first neighbour of every online node is its optimal match,
+            // BALANCE breaks ties towards it
+            assert_eq!(balance.ratio.mean, 1.);
+            assert!(rba.ratio.mean > 0.8 && rba.ratio.upper <= 1.);
+        }
+    }
+
+    #[test]
+    fn rba_balance_test() {
+        // BALANCE is 1/2 on the z-graph with a single unit, and tends to
+        // 1 - 1/e as the capacities grow
+        let mut previous = 0.;
+        for (c, ratio) in [(1, 0.5), (3, 0.6), (6, 0.65)] {
+            let graph = example::z_graph_with_capacity(10, 100, c);
+            let balance = graph.ALG::<Balance>(1) / graph.OPT();
+            assert!((balance - ratio).abs() < 1e-9);
+            assert!(balance > previous);
+            previous = balance;
+        }
+        let bound = 1. - 1. / std::f64::consts::E;
+        for c in [1, 4] {
+            let graph = example::z_graph_with_capacity(10, 100, c);
+            for estimation in [
+                Estimator::new(500, 0).estimate::<RBA, _>(&graph).unwrap(),
+                Estimator::new(500, 0)
+                    .estimate::<Ranking, _>(&graph)
+                    .unwrap(),
+            ] {
+                assert!(estimation.ratio.mean > bound && estimation.ratio.upper <= 1.);
+            }
+        }
+        let graph = example::random(6, 60, 0.3, 6, &mut StdRng::seed_from_u64(0));
+        for estimation in [
+            Estimator::new(1000, 0).estimate::<RBA, _>(&graph).unwrap(),
+            Estimator::new(1000, 0)
+                .estimate::<Balance, _>(&graph)
+                .unwrap(),
+        ] {
+            assert!(estimation.ratio.mean > bound && estimation.ratio.upper <= 1.);
+        }
+    }
+
+    #[test]
+    fn heterogeneous_reusable_violation_test() {
+        use onlinematching::error::Error;
+        use onlinematching::papers::reuseableresource::heterogeneous::{
+            algorithm::ReusableAlgorithm, OfflineInfo,
+        };
+        use onlinematching::papers::validation::Violation;
+        // Takes the first neighbour, in use or not.
+        struct First;
+
+        impl ReusableAlgorithm<OfflineInfo> for First {
+            fn init(_offline_info: OfflineInfo) -> Self {
+                First
+            }
+
+            fn release(&mut self, _offline_node: usize, _matched_at: usize) {}
+
+            fn dispatch(&mut self, online_adjacent: &Vec<usize>) -> Option<usize> {
+                online_adjacent.first().copied()
+            }
+
+            fn alg_output(self) -> f64 {
+                0.
+            }
+        }
+
+        let edges = vec![(1, 1), (1, 2), (1, 3)];
+        let graph =
+            Bigraph::from_edges(&edges).into_heterogeneous_reuseable(vec![Duration::Fixed(2)]);
+        assert_eq!(
+            graph
+                .run::<First>(&mut StdRng::seed_from_u64(0))
+                .unwrap_err(),
+            Violation::Busy {
+                online: 1,
+                offline: 0,
+                available_at: 2
+            }
+        );
+        // with two units the third arrival waits for the first one back
+        let graph = Bigraph::from_edges(&edges)
+            .into_reuseable_with_capacities(vec![Duration::Fixed(5)], vec![2]);
+        assert_eq!(
+            graph
+                .run::<First>(&mut StdRng::seed_from_u64(0))
+                .unwrap_err(),
+            Violation::Busy {
+                online: 2,
+                offline: 0,
+                available_at: 5
+            }
+        );
+        let outcome = graph.run::<RBA>(&mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(outcome.objective, 2.);
+        assert!(!outcome.offline[0].available);
+        assert_eq!(
+            Bigraph::from_edges(&edges)
+                .try_into_reuseable_with_capacities(vec![Duration::Fixed(5)], vec![2, 1])
+                .unwrap_err(),
+            Error::CapacityLengthMismatch {
+                offline_size: 1,
+                capacities: 2
+            }
+        );
+        assert_eq!(
+            Bigraph::from_edges(&edges)
+                .try_into_heterogeneous_reuseable(vec![Duration::Random(vec![(1, 0.5)])])
+                .unwrap_err(),
+            Error::InvalidDuration { offline: 1 }
+        );
+        assert_eq!(
+            Bigraph::from_edges(&edges)
+                .try_into_heterogeneous_reuseable(vec![])
+                .unwrap_err(),
+            Error::DurationLengthMismatch {
+                offline_size: 1,
+                durations: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod test_known_iid {
+    use onlinematching::bigraph::Bigraph;
+    use onlinematching::papers::known_iid::graph::example;
+    use onlinematching::papers::kvv90;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn capacitated_matching_test() {
+        use onlinematching::offline::flow::capacitated_matching;
+        // two online nodes of capacity 1.5 share offline node 0,
+        // the second one also reaches offline node 1
+        let (value, flows) = capacitated_matching(&[1., 1.], &[1.5, 1.5], &[vec![0], vec![0, 1]]);
+        assert!((value - 2.).abs() < 1e-9);
+        let offline_0: f64 = flows[0][0] + flows[1][0];
+        assert!(offline_0 <= 1. + 1e-9);
+        assert!((flows[1][1] - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn known_iid_opt_test() {
+        let n = 20;
+        let instance = example::upper_triangular(n);
+        assert!((instance.fractional_OPT() - n as f64).abs() < 1e-9);
+        let opt = instance.expected_OPT_with_rng(200, &mut StdRng::seed_from_u64(0));
+        assert!(opt < instance.fractional_OPT());
+        assert!(opt > n as f64 / 2.);
+    }
+
+    #[test]
+    fn known_iid_ranking_test() {
+        let n = 20;
+        let instance = example::upper_triangular(n);
+        let mut rng = StdRng::seed_from_u64(0);
+        let opt = instance.expected_OPT_with_rng(500, &mut rng);
+        let alg = instance.expected_ALG_with_rng::<kvv90::Ranking, _>(500, &mut rng);
+        let ratio = alg / opt;
+        println!("opt = {:?}, alg = {:?}, ratio = {:?}", opt, alg, ratio);
+        assert!(ratio > 0.6 && ratio <= 1.);
+
+        let outcome = instance
+            .run::<kvv90::Ranking, _>(&mut StdRng::seed_from_u64(1))
+            .unwrap();
+        assert_eq!(outcome.decisions.len(), n);
+        assert_eq!(outcome.matched_edges().len() as f64, outcome.objective);
+    }
+
+    #[test]
+    fn known_iid_error_test() {
+        use onlinematching::error::Error;
+        let graph = || Bigraph::from_edges(&vec![(0, 0), (1, 0), (1, 1)]);
+        assert_eq!(
+            graph().try_into_known_iid(vec![1.], 3).unwrap_err(),
+            Error::RateLengthMismatch { types: 2, rates: 1 }
+        );
+        assert_eq!(
+            graph().try_into_known_iid(vec![1., -1.], 3).unwrap_err(),
+            Error::InvalidRate {
+                online: 1,
+                rate: -1.
+            }
+        );
+        assert_eq!(
+            graph().try_into_known_iid(vec![0., 0.], 3).unwrap_err(),
+            Error::AllRatesZero
+        );
+        // a type which never arrives is fine
+        let instance = graph().into_known_iid(vec![0., 2.], 3);
+        assert_eq!(instance.info().distribution, vec![0., 1.]);
+        let types = instance.sample_arrivals(&mut StdRng::seed_from_u64(0));
+        assert_eq!(types, vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn suggested_matchings_test() {
+        use onlinematching::papers::known_iid::{fmmm09::FMMM09, two_choice::TwoChoiceLpRounding};
+        let mut rng = StdRng::seed_from_u64(0);
+        let n = 20;
+        let mut edges = vec![];
+        for v in 0..n {
+            edges.push((v, v));
+            edges.push(((v + 1) % n, v));
+            edges.push(((v + 7) % n, v));
+        }
+        let random_like = Bigraph::from_edges(&edges).into_known_iid(vec![1.; n], n);
+        for instance in [example::upper_triangular(n), random_like] {
+            let opt = instance.expected_OPT_with_rng(1000, &mut rng);
+            let fmmm = instance.expected_ALG_with_rng::<FMMM09, _>(1000, &mut rng) / opt;
+            let two_choice =
+                instance.expected_ALG_with_rng::<TwoChoiceLpRounding, _>(1000, &mut rng) / opt;
+            println!("fmmm09 = {:?}, two_choice = {:?}", fmmm, two_choice);
+            assert!(fmmm > 0.67 && fmmm <= 1.);
+            // measured at about 0.83 on both, there is no bound to check
+            assert!(two_choice > 0.8 && two_choice <= 1.);
+        }
+    }
+
+    #[test]
+    fn suggested_matchings_rates_test() {
+        use onlinematching::papers::known_iid::{fmmm09::FMMM09, two_choice::TwoChoiceLpRounding};
+        // the two types can't be told apart, together they are expected
+        // to arrive 4 times and both offline nodes can be matched
+        let graph = Bigraph::from_edges(&vec![(0, 0), (1, 0), (0, 1), (1, 1), (2, 2)]);
+        let instance = graph.into_known_iid(vec![1., 1., 0.], 4);
+        let mut rng = StdRng::seed_from_u64(0);
+        let fmmm = instance.expected_ALG_with_rng::<FMMM09, _>(500, &mut rng);
+        let two_choice = instance.expected_ALG_with_rng::<TwoChoiceLpRounding, _>(500, &mut rng);
+        assert!(fmmm > 1.5 && fmmm <= 2.);
+        // with a rate of 4 a merged type suggests no neighbour about half
+        // of the times, as the LP value of an edge is at most 1
+        assert!(two_choice > 1.3 && two_choice <= 2.);
+        let same = instance
+            .expected_ALG_with_rng::<TwoChoiceLpRounding, _>(500, &mut StdRng::seed_from_u64(3));
+        let again = instance
+            .expected_ALG_with_rng::<TwoChoiceLpRounding, _>(500, &mut StdRng::seed_from_u64(3));
+        assert_eq!(same, again);
+    }
+
+    #[test]
+    fn known_iid_instance_test() {
+        use onlinematching::papers::estimator::Estimator;
+        use onlinematching::papers::instance::{mean_ratio, Instance, Simulate};
+        use onlinematching::papers::known_iid::{fmmm09::FMMM09, two_choice::TwoChoiceLpRounding};
+        let n = 20;
+        let instance = example::upper_triangular(n);
+        // the online keys of the instance are its types
+        assert_eq!(instance.online_keys().len(), n);
+        assert_eq!(instance.arrivals().count(), n);
+        let instance =
+            Bigraph::from_edges(&vec![(0, 0), (1, 0), (1, 1)]).into_known_iid(vec![1., 1.], 5);
+        assert_eq!(instance.online_size(), 5);
+        let outcome =
+            Simulate::<kvv90::Ranking>::simulate(&instance, &mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(outcome.decisions.len(), 5);
+        assert!(outcome.decisions.iter().all(|d| d.online < 2));
+        let instance = example::upper_triangular(n);
+        assert_eq!(instance.offline_info(), instance.info());
+        assert_eq!(Instance::OPT(&instance), instance.fractional_OPT());
+        let outcome =
+            Simulate::<FMMM09>::simulate(&instance, &mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(outcome.decisions.len(), n);
+        let ratio =
+            mean_ratio::<TwoChoiceLpRounding, _>(&instance, 200, &mut StdRng::seed_from_u64(0))
+                .unwrap();
+        assert!(ratio > 0.6 && ratio <= 1.);
+        let estimation = Estimator::new(200, 0)
+            .estimate::<FMMM09, _>(&instance)
+            .unwrap();
+        assert_eq!(estimation.opt, n as f64);
+        assert!(estimation.ratio.mean > 0.6 && estimation.ratio.mean <= 1.);
+
+        // the adversarial algorithms don't use the distribution
+        let ranking = Estimator::new(200, 0)
+            .estimate::<kvv90::Ranking, _>(&instance)
+            .unwrap();
+        let random = Estimator::new(200, 0)
+            .estimate::<kvv90::Random, _>(&instance)
+            .unwrap();
+        assert!(random.alg.mean < ranking.alg.mean);
+        let ratio =
+            mean_ratio::<kvv90::Random, _>(&instance, 200, &mut StdRng::seed_from_u64(0)).unwrap();
+        assert!(ratio > 0.5 && ratio <= 1.);
+    }
+
+    #[test]
+    fn fmmm09_rare_type_test() {
+        use onlinematching::papers::known_iid::fmmm09::FMMM09;
+        // type 1 is expected to arrive 0.4 times, it still gets a copy
+        // and takes its only neighbour when it does arrive
+        let graph = Bigraph::from_edges(&vec![(0, 0), (1, 1)]);
+        let instance = graph.into_known_iid(vec![0.9, 0.1], 4);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut arrived = false;
+        for _ in 0..50 {
+            let outcome = instance.run::<FMMM09, _>(&mut rng).unwrap();
+            if let Some(decision) = outcome.decisions.iter().find(|d| d.online == 1) {
+                arrived = true;
+                assert_eq!(decision.offline, Some(1));
+            }
+        }
+        assert!(arrived);
+    }
+}
@@ -54,4 +54,316 @@ mod test_stochastic_reward {
         let ratio = alg / opt;
         println!("opt = {:?}, alg = {:?}, ratio = {:?}", opt, alg, ratio);
     }
+
+    #[test]
+    fn test_opt() {
+        let m = 20;
+        let sr = mp12::example::gk(3, m);
+        assert!((sr.OPT() - 3.).abs() < 1e-6);
+
+        // a single offline node probed m times with probability 1 / m
+        let sr = mp12::example::simplist(m);
+        assert!((sr.OPT() - 1.).abs() < 1e-6);
+        let adaptive_opt = 1. - (1. - 1. / m as f64).powi(m as i32);
+        assert!((sr.adaptive_OPT() - adaptive_opt).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_seeded_adaptive_alg() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let sr = mp12::example::gk(3, 10);
+        let alg1 = sr.adaptive_ALG_with_rng::<mp12::Balance>(100, &mut StdRng::seed_from_u64(7));
+        let alg2 = sr.adaptive_ALG_with_rng::<mp12::Balance>(100, &mut StdRng::seed_from_u64(7));
+        assert_eq!(alg1, alg2);
+        let alg1 = sr.adaptive_ALG_with_rng::<ranking::Ranking>(100, &mut StdRng::seed_from_u64(7));
+        let alg2 = sr.adaptive_ALG_with_rng::<ranking::Ranking>(100, &mut StdRng::seed_from_u64(7));
+        assert_eq!(alg1, alg2);
+    }
+
+    #[test]
+    fn test_adaptive_run() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let sr = mp12::example::gk(3, 5);
+        let outcome = sr
+            .adaptive_run::<mp12::Balance>(&mut StdRng::seed_from_u64(1))
+            .unwrap();
+        let successes = outcome
+            .decisions
+            .iter()
+            .filter(|d| d.success == Some(true))
+            .count();
+        assert_eq!(successes as f64, outcome.objective);
+        assert_eq!(outcome.matched_edges().len(), successes);
+        let unavailable = outcome.offline.iter().filter(|u| !u.available).count();
+        assert_eq!(unavailable, successes);
+    }
+
+    #[test]
+    fn test_probability_out_of_range() {
+        use onlinematching::error::Error;
+        use onlinematching::weightedbigraph::WBigraph;
+        let g = WBigraph::from_edges(&vec![((0, 0), 0.5), ((1, 0), 1.5)]);
+        assert_eq!(
+            g.try_into_stochastic_reward().unwrap_err(),
+            Error::ProbabilityOutOfRange {
+                offline: 1,
+                online: 0,
+                prob: 1.5
+            }
+        );
+    }
+
+    #[test]
+    fn test_parallel_edges() {
+        use onlinematching::weightedbigraph::WBigraph;
+        use rand::{rngs::StdRng, SeedableRng};
+        // the edge keeps the larger probability, so every probe succeeds
+        let edges = vec![((0, 0), 0.5), ((0, 0), 1.), ((1, 1), 1.)];
+        let sr = WBigraph::from_edges(&edges).into_stochastic_reward();
+        let outcome = sr
+            .adaptive_run::<mp12::Balance>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(outcome.objective, 2.);
+        assert!((sr.OPT() - 2.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_stream_adaptive_alg() {
+        use onlinematching::papers::stochastic_reward::graph::stream_adaptive_ALG;
+        use rand::{rngs::StdRng, SeedableRng};
+        let m = 20;
+        let sr = mp12::example::gk(3, m);
+        let arrivals = sr.weighted_bigraph.v_adjacency_list.clone();
+        let offline_size = sr.weighted_bigraph.u_nodes.len();
+        for seed in 0..5 {
+            let alg =
+                sr.adaptive_ALG_with_rng::<ranking::Ranking>(1, &mut StdRng::seed_from_u64(seed));
+            let streamed = stream_adaptive_ALG::<ranking::Ranking, _>(
+                offline_size,
+                arrivals.iter(),
+                &mut StdRng::seed_from_u64(seed),
+            );
+            assert_eq!(streamed, Ok(alg));
+        }
+    }
+
+    #[test]
+    fn test_random_order() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let m = 20;
+        let sr = mp12::example::gk(2, m);
+        let t = 2000;
+        let alg =
+            sr.random_order_adaptive_ALG::<ranking::Ranking>(t, &mut StdRng::seed_from_u64(0));
+        assert!(alg > 0. && alg <= sr.OPT());
+        let same =
+            sr.random_order_adaptive_ALG::<ranking::Ranking>(t, &mut StdRng::seed_from_u64(0));
+        assert_eq!(alg, same);
+        let outcome = sr
+            .random_order_adaptive_run::<mp12::Balance>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(outcome.decisions.len(), sr.weighted_bigraph.v_nodes.len());
+    }
+
+    #[test]
+    fn test_exact_ranking() {
+        use onlinematching::weightedbigraph::WBigraph;
+        // ranks u0 < u1: v0 probes u0 and v1 only probes it again if it
+        // failed, 1/2 + 1/4; ranks u1 < u0: both probes go to distinct nodes
+        let edges = vec![((0, 0), 0.5), ((1, 0), 0.5), ((0, 1), 0.5)];
+        let sr = WBigraph::from_edges(&edges).into_stochastic_reward();
+        assert!((sr.exact_ranking_ALG() - (0.75 + 1.) / 2.).abs() < 1e-12);
+
+        let sr = mp12::example::simplist(10);
+        assert!((sr.exact_ranking_ALG() - sr.adaptive_OPT()).abs() < 1e-12);
+
+        let sr = mp12::example::gk(2, 2);
+        let exact = sr.exact_ranking_ALG();
+        let sampled = sr.adaptive_ALG_with_rng::<ranking::Ranking>(
+            20000,
+            &mut rand::SeedableRng::seed_from_u64(0),
+        );
+        assert!((exact - sampled).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_non_adaptive() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let sr = mp12::example::gk(2, 10);
+        // the assignment of the non-adaptive BALANCE is deterministic, so
+        // its expected ALG follows from a single run
+        let outcome = sr
+            .non_adaptive_run::<mp12::NonAdaptiveBalance>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        let mut unmatched = vec![1.; outcome.offline.len()];
+        for decision in outcome.decisions.iter() {
+            let (u, p) = (decision.offline.unwrap(), decision.weight.unwrap());
+            unmatched[u] *= 1. - p;
+        }
+        let expected: f64 = unmatched.iter().map(|q| 1. - q).sum();
+        let mut rng = StdRng::seed_from_u64(0);
+        let balance = sr.non_adaptive_ALG_with_rng::<mp12::NonAdaptiveBalance>(20000, &mut rng);
+        assert!((balance - expected).abs() < 0.02);
+        // the adaptivity gap
+        let adaptive = sr.adaptive_ALG_with_rng::<mp12::Balance>(20000, &mut rng);
+        assert!(adaptive > balance + 0.03);
+        let ranking = sr.non_adaptive_ALG_with_rng::<ranking::NonAdaptiveRanking>(20000, &mut rng);
+        assert!(sr.exact_ranking_ALG() > ranking + 0.1);
+    }
+
+    #[test]
+    fn test_non_adaptive_run() {
+        use onlinematching::papers::stochastic_reward::graph::algorithm::NoneAdaptiveAlgorithm;
+        use onlinematching::papers::validation::Violation;
+        use rand::{rngs::StdRng, SeedableRng};
+        // every online node is committed to the only offline node, the
+        // probes after the first success are wasted (one succeeds with this
+        // seed)
+        let sr = mp12::example::simplist(10);
+        let outcome = sr
+            .non_adaptive_run::<ranking::NonAdaptiveRanking>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        let first = outcome
+            .decisions
+            .iter()
+            .position(|decision| decision.success == Some(true));
+        assert!(outcome.decisions.iter().all(|d| d.offline == Some(0)));
+        assert!(outcome
+            .decisions
+            .iter()
+            .skip(first.unwrap() + 1)
+            .all(|d| d.success == Some(false)));
+        assert_eq!(outcome.objective, 1.);
+        assert!((outcome.offline[0].load - 1.).abs() < 1e-9);
+
+        // Probes with a made up probability.
+        struct Halves;
+
+        impl NoneAdaptiveAlgorithm<(usize, f64), usize> for Halves {
+            fn init(_lenth: usize) -> Self {
+                Halves
+            }
+
+            fn dispatch(&mut self, online_adjacent: &Vec<(usize, f64)>) -> Option<(usize, f64)> {
+                online_adjacent.first().map(|&(u, _)| (u, 0.5))
+            }
+        }
+
+        assert_eq!(
+            sr.non_adaptive_run::<Halves>(&mut StdRng::seed_from_u64(0))
+                .unwrap_err(),
+            Violation::WrongProbability {
+                online: 0,
+                offline: 0,
+                expected: 0.1,
+                found: 0.5
+            }
+        );
+    }
+
+    #[test]
+    fn test_unequal_vanishing() {
+        use onlinematching::papers::estimator::Estimator;
+        use onlinematching::papers::stochastic_reward::{gu20, hz20};
+        use rand::{rngs::StdRng, SeedableRng};
+        let mut rng = StdRng::seed_from_u64(0);
+        // online type v is adjacent to the offline nodes v, ..., 4
+        let edges: Vec<(usize, usize)> = (0..5).flat_map(|v| (v..5).map(move |u| (u, v))).collect();
+        let m = 20;
+        let sr = gu20::example::heterogeneous(&edges, m, &mut rng);
+        assert_eq!(sr.weighted_bigraph.v_nodes.len(), 5 * m);
+        for online_adj in sr.weighted_bigraph.v_adjacency_list.iter() {
+            assert!(online_adj.iter().all(|&(_, p)| p < 2. / m as f64));
+        }
+        let estimator = Estimator::new(10000, 0);
+        let balance = estimator.estimate::<mp12::Balance, _>(&sr).unwrap().ratio;
+        let sbalance = estimator.estimate::<gu20::SBalance, _>(&sr).unwrap().ratio;
+        let primal_dual = estimator
+            .estimate::<hz20::PrimalDual, _>(&sr)
+            .unwrap()
+            .ratio;
+        for ratio in [balance, sbalance, primal_dual] {
+            assert!(
+                ratio.mean > 0.55 && ratio.upper <= 1.,
+                "ratio = {:?}",
+                ratio
+            );
+        }
+        // measured at about 0.78 for BALANCE and the primal-dual and 0.72
+        // for SBALANCE, whose score gives up more for the probability
+        assert!(sbalance.upper < balance.lower);
+        assert!(sbalance.upper < primal_dual.lower);
+        let t = 2000;
+
+        let sr = gu20::example::random(10, 100, 0.3, 0.2, &mut rng);
+        for online_adj in sr.weighted_bigraph.v_adjacency_list.iter() {
+            assert!(online_adj.iter().all(|&(_, p)| 0. < p && p <= 0.2));
+        }
+        let ratio = sr.adaptive_ALG_with_rng::<gu20::SBalance>(t, &mut rng) / sr.OPT();
+        assert!(ratio > 0.55 && ratio <= 1., "ratio = {}", ratio);
+    }
+
+    #[test]
+    fn test_primal_dual() {
+        use onlinematching::papers::stochastic_reward::graph::algorithm::AdaptiveAlgorithm;
+        use onlinematching::papers::stochastic_reward::{gu20, hz20::PrimalDual};
+        use rand::{rngs::StdRng, SeedableRng};
+        let mut rng = StdRng::seed_from_u64(0);
+        let sr = gu20::example::random(5, 40, 0.5, 0.3, &mut rng);
+        let online_size = sr.weighted_bigraph.v_nodes.len();
+        // the dual is the sum of the probed probabilities, on average the ALG
+        let trials = 4000;
+        let mut dual_sum = 0.;
+        let mut alg_sum = 0.;
+        for seed in 0..trials {
+            let mut alg: PrimalDual = PrimalDual::init_with_rng(5, StdRng::seed_from_u64(seed));
+            let mut probed = 0.;
+            for online_adj in sr.iter() {
+                alg.next_online();
+                let probe = alg.dispatch(online_adj);
+                probed += probe.map_or(0., |(_, p)| p);
+                alg.query_success(probe);
+            }
+            assert!((alg.dual() - probed).abs() < 1e-9);
+            assert_eq!(alg.beta().len(), online_size);
+            dual_sum += alg.dual();
+            alg_sum += alg.alg_output();
+        }
+        assert!((dual_sum - alg_sum).abs() / (trials as f64) < 0.05);
+    }
+
+    #[test]
+    fn test_gain_sharing() {
+        use onlinematching::papers::stochastic_reward::graph::algorithm::AdaptiveAlgorithm;
+        use onlinematching::papers::stochastic_reward::gu20;
+        use onlinematching::papers::stochastic_reward::hz20::{Exponential, PrimalDual};
+        use rand::{rngs::StdRng, SeedableRng};
+        // with f(l) = 1 - e^(-l) it probes like BALANCE
+        let sr = gu20::example::random(5, 40, 0.5, 0.3, &mut StdRng::seed_from_u64(0));
+        for seed in 0..20 {
+            let mut exponential: PrimalDual<Exponential> =
+                PrimalDual::init_with_rng(5, StdRng::seed_from_u64(seed));
+            let mut balance = mp12::Balance::init_with_rng(5, StdRng::seed_from_u64(seed));
+            for online_adj in sr.iter() {
+                exponential.next_online();
+                let probe = exponential.dispatch(online_adj);
+                assert_eq!(probe, balance.dispatch(online_adj));
+                assert_eq!(
+                    exponential.query_success(probe),
+                    balance.query_success(probe)
+                );
+            }
+        }
+
+        // offline node 1 has load 0.2: BALANCE prefers 0.1 * e^0 to
+        // 0.12 * e^(-0.2), the tuned f penalizes the load less
+        let arrivals = [vec![(1, 0.2)], vec![(0, 0.1), (1, 0.12)]];
+        let mut tuned: PrimalDual = PrimalDual::init_with_rng(2, StdRng::seed_from_u64(0));
+        let mut balance = mp12::Balance::init_with_rng(2, StdRng::seed_from_u64(0));
+        tuned.next_online();
+        assert_eq!(tuned.dispatch(&arrivals[0]), balance.dispatch(&arrivals[0]));
+        tuned.next_online();
+        assert_eq!(tuned.dispatch(&arrivals[1]), Some((1, 0.12)));
+        assert_eq!(balance.dispatch(&arrivals[1]), Some((0, 0.1)));
+    }
 }
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod test_vertex_weighted {
+    use onlinematching::bigraph::Bigraph;
+    use onlinematching::papers::vertex_weighted::{agkm11::PerturbedGreedy, graph::example};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn vertex_weighted_opt_test() {
+        use onlinematching::offline::matching::{hopcroft_karp, vertex_weighted_matching};
+        // v1 can take the heavy u2 or the light u1, v2 only u2
+        let edges = vec![(1, 1), (2, 1), (2, 2)];
+        let instance = Bigraph::from_edges(&edges).into_vertex_weighted(vec![1., 5.]);
+        let (weight, matching) = instance.opt_matching();
+        assert_eq!(weight, 6.);
+        assert_eq!(matching, vec![Some(0), Some(1)]);
+        assert_eq!(instance.OPT(), 6.);
+
+        // against every subset of offline nodes which can be matched together
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let offline_size = rng.gen_range(1..7);
+            let online_adjacency_list: Vec<Vec<usize>> = (0..rng.gen_range(1..7))
+                .map(|_| (0..offline_size).filter(|_| rng.gen_bool(0.4)).collect())
+                .collect();
+            let weights: Vec<f64> = (0..offline_size)
+                .map(|_| rng.gen_range(0..10) as f64)
+                .collect();
+            let mut best = 0.;
+            for mask in 0..(1 << offline_size) {
+                let restricted: Vec<Vec<usize>> = online_adjacency_list
+                    .iter()
+                    .map(|adj| {
+                        adj.iter()
+                            .copied()
+                            .filter(|u| mask & (1 << u) != 0)
+                            .collect()
+                    })
+                    .collect();
+                let size = hopcroft_karp(offline_size, &restricted).0;
+                if size == (mask as usize).count_ones() as usize {
+                    let weight: f64 = (0..offline_size)
+                        .filter(|u| mask & (1 << u) != 0)
+                        .map(|u| weights[u])
+                        .sum();
+                    best = f64::max(best, weight);
+                }
+            }
+            let (weight, matching) = vertex_weighted_matching(&weights, &online_adjacency_list);
+            assert_eq!(weight, best);
+            let mut used = vec![false; offline_size];
+            for (v, u) in matching.iter().enumerate() {
+                if let &Some(u) = u {
+                    assert!(online_adjacency_list[v].contains(&u) && !used[u]);
+                    used[u] = true;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn perturbed_greedy_test() {
+        use onlinematching::papers::estimator::Estimator;
+        use onlinematching::papers::kvv90::Ranking;
+        let n = 30;
+        let mut rng = StdRng::seed_from_u64(0);
+        let weights: Vec<f64> = (0..n).map(|_| rng.gen_range(1. ..10.)).collect();
+        let instance = example::upper_triangular(weights.clone());
+        assert!((instance.OPT() - weights.iter().sum::<f64>()).abs() < 1e-9);
+        let estimation = Estimator::new(2000, 1)
+            .estimate::<PerturbedGreedy, _>(&instance)
+            .unwrap();
+        println!("perturbed greedy: {:?}", estimation.ratio);
+        assert!(estimation.ratio.lower > 1. - 1. / std::f64::consts::E);
+
+        // with equal weights it is as good as Ranking and not better than 1 - 1/e
+        let instance = example::upper_triangular(vec![1.; n]);
+        let greedy = Estimator::new(2000, 2)
+            .estimate::<PerturbedGreedy, _>(&instance)
+            .unwrap();
+        let ranking = onlinematching::papers::kvv90::example::ranking_worst_case(n);
+        let ranking = Estimator::new(2000, 3)
+            .estimate::<Ranking, _>(&ranking)
+            .unwrap();
+        assert!((greedy.ratio.mean - ranking.ratio.mean).abs() < 0.02);
+        assert!(greedy.ratio.mean < 0.7);
+
+        // the unweighted algorithms run on the model too
+        let alg = instance.ALG_with_rng::<Ranking, usize>(&mut rng);
+        assert!(alg >= 1. && alg <= n as f64);
+    }
+
+    #[test]
+    fn vertex_weighted_run_test() {
+        let edges = vec![(1, 1), (2, 1), (2, 2)];
+        let instance = Bigraph::from_edges(&edges).into_vertex_weighted(vec![1., 5.]);
+        let outcome = instance
+            .run::<PerturbedGreedy, Vec<f64>>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        // v1 takes u1 only if 5 psi(y_u2) < psi(y_u1), which this seed
+        // doesn't draw
+        assert_eq!(outcome.decisions[0].offline, Some(2));
+        assert_eq!(outcome.decisions[0].weight, Some(5.));
+        assert_eq!(outcome.decisions[1].offline, None);
+        assert_eq!(outcome.objective, 5.);
+    }
+
+    #[test]
+    fn vertex_weighted_error_test() {
+        use onlinematching::error::Error;
+        let graph = || Bigraph::from_edges(&vec![(1, 1), (2, 1)]);
+        assert_eq!(
+            graph().try_into_vertex_weighted(vec![1.]).unwrap_err(),
+            Error::WeightLengthMismatch {
+                offline_size: 2,
+                weights: 1
+            }
+        );
+        assert_eq!(
+            graph()
+                .try_into_vertex_weighted(vec![1., f64::INFINITY])
+                .unwrap_err(),
+            Error::InvalidWeight {
+                offline: 2,
+                weight: f64::INFINITY
+            }
+        );
+        assert!(graph().try_into_vertex_weighted(vec![0., 1.]).is_ok());
+    }
+}
@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod test_weighted_stochastic {
+    use onlinematching::papers::estimator::Estimator;
+    use onlinematching::papers::stochastic_reward::{
+        gu20::WeightedSBalance, mp12, ranking::PerturbedRanking, weighted::example,
+    };
+    use onlinematching::weightedbigraph::WBigraph;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn weighted_stochastic_opt_test() {
+        let sr = mp12::example::gk(2, 5);
+        let weighted = WBigraph::from_edges(&sr.weighted_bigraph.nodes_edges)
+            .into_weighted_stochastic_reward(vec![1.; 2], 1);
+        assert!((weighted.OPT() - sr.OPT()).abs() < 1e-9);
+
+        // more patience relaxes the benchmark
+        let mut previous = 0.;
+        for patience in 1..4 {
+            let sr = example::random(8, 8, 0.4, patience, &mut StdRng::seed_from_u64(0));
+            assert!(sr.OPT() >= previous - 1e-9);
+            previous = sr.OPT();
+        }
+    }
+
+    #[test]
+    fn patience_test() {
+        let alg = |patience| {
+            let sr = example::random(10, 10, 0.4, patience, &mut StdRng::seed_from_u64(0));
+            sr.ALG_with_rng::<WeightedSBalance, Vec<f64>>(4000, &mut StdRng::seed_from_u64(1))
+        };
+        assert!(alg(3) > alg(1) + 1.);
+
+        let sr = example::random(10, 10, 0.4, 3, &mut StdRng::seed_from_u64(0));
+        let outcome = sr
+            .run::<PerturbedRanking, Vec<f64>>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        for &v in sr.weighted_bigraph.v_nodes.iter() {
+            let probes: Vec<_> = outcome
+                .decisions
+                .iter()
+                .filter(|decision| decision.online == v)
+                .collect();
+            assert!(!probes.is_empty() && probes.len() <= 3);
+            // only the last probe may succeed, and every probe is of a
+            // different offline node
+            for (i, probe) in probes.iter().enumerate() {
+                if i + 1 < probes.len() {
+                    assert_eq!(probe.success, Some(false));
+                }
+                assert!(probes[..i].iter().all(|p| p.offline != probe.offline));
+            }
+        }
+        let matched: f64 = outcome
+            .offline
+            .iter()
+            .zip(sr.weights().iter())
+            .filter(|(state, _)| !state.available)
+            .map(|(_, &weight)| weight)
+            .sum();
+        assert!((outcome.objective - matched).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weighted_stochastic_estimator_test() {
+        let sr = example::random(10, 10, 0.4, 2, &mut StdRng::seed_from_u64(2));
+        for estimation in [
+            Estimator::new(1000, 0)
+                .estimate::<WeightedSBalance, _>(&sr)
+                .unwrap(),
+            Estimator::new(1000, 0)
+                .estimate::<PerturbedRanking, _>(&sr)
+                .unwrap(),
+        ] {
+            assert!(estimation.ratio.mean > 0.5 && estimation.ratio.upper <= 1.);
+        }
+        // the unweighted algorithms run too, and count the matches
+        let matches = sr.ALG_with_rng::<mp12::Balance, usize>(100, &mut StdRng::seed_from_u64(0));
+        assert!(matches > 0. && matches <= 10.);
+    }
+
+    #[test]
+    fn next_online_test() {
+        use onlinematching::papers::stochastic_reward::graph::algorithm::AdaptiveAlgorithm;
+        use onlinematching::papers::stochastic_reward::hz20::PrimalDual;
+        // Probes its neighbours in order, always failing, and counts the
+        // online nodes and the probes of the current one.
+        struct Counting {
+            online: usize,
+            probes: Vec<usize>,
+        }
+
+        impl AdaptiveAlgorithm<(usize, f64), usize> for Counting {
+            fn init(_length: usize) -> Self {
+                Counting {
+                    online: 0,
+                    probes: vec![],
+                }
+            }
+
+            fn next_online(&mut self) {
+                self.online += 1;
+                self.probes.push(0);
+            }
+
+            fn dispatch(&mut self, online_adjacent: &Vec<(usize, f64)>) -> Option<(usize, f64)> {
+                *self.probes.last_mut().unwrap() += 1;
+                online_adjacent.first().copied()
+            }
+
+            fn query_success(&mut self, offline_node: Option<(usize, f64)>) -> Option<bool> {
+                offline_node.map(|_| false)
+            }
+
+            fn alg_output(self) -> f64 {
+                assert_eq!(self.online, 2);
+                // v1 has two neighbours and patience 3, v2 one neighbour
+                assert_eq!(self.probes, vec![3, 2]);
+                0.
+            }
+        }
+
+        let edges = vec![((1, 1), 0.5), ((2, 1), 0.5), ((1, 2), 0.5)];
+        let sr = WBigraph::from_edges(&edges).into_weighted_stochastic_reward(vec![1., 2.], 3);
+        sr.run::<Counting, usize>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        // the primal-dual keeps one beta per online node over its probes
+        let sr = example::random(10, 10, 0.4, 3, &mut StdRng::seed_from_u64(0));
+        let mut alg: PrimalDual = PrimalDual::init_with_rng(10, StdRng::seed_from_u64(0));
+        let mut online = 0;
+        for online_adj in sr.weighted_bigraph.v_adjacency_list.iter() {
+            alg.next_online();
+            online += 1;
+            let mut offered = online_adj.clone();
+            for _ in 0..3 {
+                let probe = alg.dispatch(&offered);
+                if probe.is_none() || alg.query_success(probe) == Some(true) {
+                    break;
+                }
+                offered.retain(|&(u, _)| Some(u) != probe.map(|(u, _)| u));
+            }
+        }
+        assert_eq!(alg.beta().len(), online);
+        assert!(sr
+            .run::<PrimalDual, usize>(&mut StdRng::seed_from_u64(0))
+            .is_ok());
+    }
+
+    #[test]
+    fn weighted_stochastic_violation_test() {
+        use onlinematching::error::Error;
+        use onlinematching::papers::stochastic_reward::graph::algorithm::AdaptiveAlgorithm;
+        use onlinematching::papers::validation::Violation;
+        // Probes its first neighbour over and over, always failing.
+        struct Stubborn {
+            first: Option<(usize, f64)>,
+        }
+
+        impl AdaptiveAlgorithm<(usize, f64), usize> for Stubborn {
+            fn init(_lenth: usize) -> Self {
+                Stubborn { first: None }
+            }
+
+            fn dispatch(&mut self, online_adjacent: &Vec<(usize, f64)>) -> Option<(usize, f64)> {
+                if self.first.is_none() {
+                    self.first = online_adjacent.first().copied();
+                }
+                self.first
+            }
+
+            fn query_success(&mut self, offline_node: Option<(usize, f64)>) -> Option<bool> {
+                offline_node.map(|_| false)
+            }
+
+            fn alg_output(self) -> f64 {
+                0.
+            }
+        }
+
+        let edges = vec![((1, 1), 0.5), ((2, 1), 0.5)];
+        let sr = WBigraph::from_edges(&edges).into_weighted_stochastic_reward(vec![1., 2.], 2);
+        assert_eq!(
+            sr.run::<Stubborn, usize>(&mut StdRng::seed_from_u64(0))
+                .unwrap_err(),
+            Violation::AlreadyProbed {
+                online: 0,
+                offline: 0
+            }
+        );
+        // with patience 1 it leaves after the first probe
+        let sr = WBigraph::from_edges(&edges).into_weighted_stochastic_reward(vec![1., 2.], 1);
+        let outcome = sr
+            .run::<Stubborn, usize>(&mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(outcome.decisions.len(), 1);
+
+        assert_eq!(
+            WBigraph::from_edges(&edges)
+                .try_into_weighted_stochastic_reward(vec![1., 2.], 0)
+                .unwrap_err(),
+            Error::InvalidPatience { patience: 0 }
+        );
+        assert_eq!(
+            WBigraph::from_edges(&edges)
+                .try_into_weighted_stochastic_reward(vec![1.], 1)
+                .unwrap_err(),
+            Error::WeightLengthMismatch {
+                offline_size: 2,
+                weights: 1
+            }
+        );
+    }
+}